@@ -0,0 +1,38 @@
+//! Compares the interpreter (`run`) to the compiled backend (`run_compiled`) on days 12 and 23,
+//! without the loop optimizer, which would hide most of the difference.
+
+use advent_of_code_2016::computer::Computer;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+const BENCHMARKS: [(&str, &str, [i32; 4]); 4] = [
+    ("day 12, part 1", "day12.txt", [0, 0, 0, 0]),
+    ("day 12, part 2", "day12.txt", [0, 0, 1, 0]),
+    ("day 23, a = 7", "day23.txt", [7, 0, 0, 0]),
+    ("day 23, a = 10", "day23.txt", [10, 0, 0, 0]),
+];
+
+fn backends(c: &mut Criterion) {
+    for &(name, input, registers) in BENCHMARKS.iter() {
+        let path = format!("{}/inputs/{}", env!("CARGO_MANIFEST_DIR"), input);
+        let input = std::fs::read_to_string(path).unwrap();
+        let mut computer = Computer::new(&input).unwrap();
+        computer.set_registers(registers);
+
+        let expected = computer.clone().run().unwrap().registers();
+        let actual = computer.clone().run_compiled().unwrap().registers();
+        assert_eq!(actual, expected, "{}", name);
+
+        let mut group = c.benchmark_group(name);
+        group.sample_size(10);
+        group.bench_function("interpreted", |b| {
+            b.iter(|| computer.clone().run().unwrap().registers())
+        });
+        group.bench_function("compiled", |b| {
+            b.iter(|| computer.clone().run_compiled().unwrap().registers())
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, backends);
+criterion_main!(benches);
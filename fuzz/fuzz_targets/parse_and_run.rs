@@ -0,0 +1,12 @@
+//! Run with `cargo fuzz run parse_and_run` from the repository root (nightly). To turn a crash
+//! into a regression test, minimize it with `cargo fuzz tmin parse_and_run <artifact>`, then copy
+//! the result to `fuzz/regressions/`, which `cargo test` replays.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../harness.rs"]
+mod harness;
+
+fuzz_target!(|data: &[u8]| harness::parse_and_run(data));
@@ -0,0 +1,103 @@
+//! Shared by the `parse_and_run` fuzz target and the regression tests, so that every crash found
+//! by the fuzzer can be replayed with `cargo test`.
+
+use advent_of_code_2016::computer::codegen::Language;
+use advent_of_code_2016::computer::{Computer, ComputerError, OverflowPolicy, Snapshot, Width};
+
+/// Steps each run may take, to keep a single input fast.
+const MAX_STEPS: usize = 10_000;
+
+/// Parse arbitrary bytes as a snapshot and as a program and, if it is valid, inspect it and run it
+/// for a bounded number of steps under every width and overflow policy, interpreted and compiled.
+/// None of it may panic, and both backends must agree.
+pub fn parse_and_run(data: &[u8]) {
+    let program = match std::str::from_utf8(data) {
+        Ok(program) => program,
+        Err(_) => return,
+    };
+    if let Err(e) = program.parse::<Snapshot>() {
+        let _ = e.to_string();
+    }
+    let computer = match Computer::new(program) {
+        Ok(computer) => computer,
+        Err(e) => {
+            let _ = e.to_string();
+            return;
+        }
+    };
+
+    let listed = Computer::new(&computer.listing().annotated().to_string())
+        .expect("the listing of a program parses back");
+    assert_eq!(listed.program(), computer.program());
+
+    let analysis = computer.analyze();
+    let _ = (
+        analysis.dead_code(),
+        analysis.dead_stores(),
+        analysis.dot().to_string(),
+    );
+    let _ = computer.transpile(Language::Rust).to_string();
+    let _ = computer.transpile(Language::C).to_string();
+
+    // Symbolic evaluation may take up to a million steps, so it is only tried on programs that
+    // halt (or fail) quickly.
+    let quick = !matches!(
+        computer.clone().run_with_limit(MAX_STEPS),
+        Err(ComputerError::StepLimitExceeded(_))
+    );
+    if quick {
+        for symbols in [&[][..], computer.register_names()] {
+            if let Ok(summary) = computer.evaluate_symbolically(symbols) {
+                for (_, polynomial) in summary.registers() {
+                    let _ = (polynomial.to_string(), polynomial.evaluate(|_| -1));
+                }
+            }
+        }
+    }
+
+    for &width in [Width::I32, Width::I64, Width::I128].iter() {
+        for &policy in [
+            OverflowPolicy::Error,
+            OverflowPolicy::Wrap,
+            OverflowPolicy::Saturate,
+        ]
+        .iter()
+        {
+            let mut computer = computer.clone();
+            computer
+                .set_width(width)
+                .set_overflow_policy(policy)
+                .set_registers([1, 2, 3, 4]);
+            let mut interpreted = computer.clone();
+            let result = interpreted.run_with_limit(MAX_STEPS).map(|_| ());
+            check_snapshot(&computer, &interpreted);
+
+            // The compiled backend has no step limit.
+            if !matches!(result, Err(ComputerError::StepLimitExceeded(_))) {
+                let mut compiled = computer.clone();
+                let compiled_result = compiled.run_compiled().map(|_| ());
+                assert_eq!(format!("{:?}", compiled_result), format!("{:?}", result));
+                assert_eq!(compiled.registers(), interpreted.registers());
+                assert_eq!(compiled.output(), interpreted.output());
+            }
+
+            let _ = computer.optimize().run_with_limit(MAX_STEPS);
+        }
+    }
+}
+
+/// The snapshot of `state` must survive its text form, and restore `computer` to the same state.
+fn check_snapshot(computer: &Computer, state: &Computer) {
+    let snapshot = state.snapshot();
+    let parsed: Snapshot = snapshot
+        .to_string()
+        .parse()
+        .expect("a snapshot parses back");
+    assert_eq!(parsed, snapshot);
+
+    let mut restored = computer.clone();
+    restored
+        .restore(&parsed)
+        .expect("a snapshot restores the computer it was taken from");
+    assert_eq!(restored.snapshot(), snapshot);
+}
@@ -0,0 +1,177 @@
+use advent_of_code_2016::computer::{Breakpoint, Computer, Register, Step};
+use std::convert::TryFrom;
+use std::io::{BufRead, Write};
+
+const USAGE: &str = "usage: asmb-debug <program.txt>";
+const HELP: &str = "\
+step [n]         execute the next n instructions (default 1)
+continue         run until a breakpoint is hit or the program halts
+break [ip]       stop before executing the instruction at ip (lists breakpoints without ip)
+watch <reg>      stop whenever the register changes
+delete [ip|reg]  remove a breakpoint or watch (all of them without argument)
+print            show the instruction pointer, registers and output
+set <reg> <val>  change the value of a register
+input <val>..    queue values for the in instructions
+disasm           list the program, as modified by tgl
+dot              print the control-flow graph of the program in Graphviz DOT format
+symbolic <reg>.. evaluate the rest of the program with the given registers left unknown
+reset            restart the program from scratch, keeping breakpoints
+help             show this message
+quit             exit the debugger";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::args().nth(1).ok_or(USAGE)?;
+    let input = std::fs::read_to_string(path)?;
+    let mut computer = Computer::new(&input)?;
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("(asmb) ");
+        std::io::stdout().flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => (),
+            ["quit"] | ["q"] => break,
+            ["help"] | ["h"] => println!("{}", HELP),
+            command => {
+                if let Err(e) = execute(&mut computer, command) {
+                    println!("error: {}", e);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn execute(computer: &mut Computer, command: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        ["step"] | ["s"] => step(computer, 1)?,
+        ["step", n] | ["s", n] => step(computer, n.parse()?)?,
+        ["continue"] | ["c"] => match computer.run_to_breakpoint()? {
+            Some(Breakpoint::InstructionPointer(ip)) => println!("breakpoint at {}", ip),
+            Some(Breakpoint::RegisterChanged(r)) => {
+                println!("{} = {}", r, computer.register(r));
+                print_current(computer);
+            }
+            Some(b) => println!("{:?}", b),
+            None => println!("halted"),
+        },
+        ["break"] | ["b"] => {
+            for b in computer.breakpoints() {
+                match b {
+                    Breakpoint::InstructionPointer(ip) => println!("break {}", ip),
+                    Breakpoint::RegisterChanged(r) => println!("watch {}", r),
+                    b => println!("{:?}", b),
+                }
+            }
+        }
+        ["break", ip] | ["b", ip] => {
+            computer.add_breakpoint(Breakpoint::InstructionPointer(ip.parse()?));
+        }
+        ["watch", r] | ["w", r] => {
+            computer.add_breakpoint(Breakpoint::RegisterChanged(Register::try_from(*r)?));
+        }
+        ["delete"] | ["d"] => {
+            computer.clear_breakpoints();
+        }
+        ["delete", target] | ["d", target] => {
+            let breakpoint = match target.parse() {
+                Ok(ip) => Breakpoint::InstructionPointer(ip),
+                Err(_) => Breakpoint::RegisterChanged(Register::try_from(*target)?),
+            };
+            computer.remove_breakpoint(breakpoint);
+        }
+        ["print"] | ["p"] => {
+            println!("ip = {}", computer.instruction_pointer());
+            for (register, value) in computer.named_registers() {
+                println!("{} = {}", register, value);
+            }
+            println!("output = {:?}", computer.output());
+        }
+        ["set", r, value] => {
+            computer.set_register(Register::try_from(*r)?, value.parse::<i128>()?)?;
+        }
+        ["input", values @ ..] => {
+            for value in values {
+                computer.input(value.parse()?);
+            }
+        }
+        ["disasm"] => {
+            let listing = computer.listing().annotated().to_string();
+            for (idx, instruction) in listing.lines().enumerate() {
+                let idx = idx as i32;
+                let marker = if idx == computer.instruction_pointer() {
+                    "=>"
+                } else {
+                    "  "
+                };
+                let breakpoint = if computer
+                    .breakpoints()
+                    .contains(&Breakpoint::InstructionPointer(idx))
+                {
+                    "*"
+                } else {
+                    " "
+                };
+                println!("{}{}{:4}  {}", marker, breakpoint, idx, instruction);
+            }
+        }
+        ["dot"] => print!("{}", computer.analyze().dot()),
+        ["symbolic", registers @ ..] => {
+            let registers = registers
+                .iter()
+                .map(|&r| Register::try_from(r))
+                .collect::<Result<Vec<_>, _>>()?;
+            print!("{}", computer.evaluate_symbolically(&registers)?);
+        }
+        ["reset"] => {
+            computer.reset();
+        }
+        _ => return Err(format!("unknown command: {} (try help)", command.join(" ")).into()),
+    }
+
+    Ok(())
+}
+
+fn step(computer: &mut Computer, n: usize) -> Result<(), Box<dyn std::error::Error>> {
+    for _ in 0..n {
+        match computer.step()? {
+            Step::Executed {
+                instruction_pointer,
+                instruction,
+            } => println!("{:4}  {}", instruction_pointer, instruction),
+            Step::Optimized {
+                instruction_pointer,
+            } => println!("{:4}  (optimized loop)", instruction_pointer),
+            Step::Blocked {
+                instruction_pointer,
+            } => {
+                println!("{:4}  waiting for input", instruction_pointer);
+                break;
+            }
+            Step::Halted => {
+                println!("halted");
+                break;
+            }
+        }
+    }
+
+    print_current(computer);
+    Ok(())
+}
+
+fn print_current(computer: &Computer) {
+    let ip = computer.instruction_pointer();
+    match computer.program().get(ip as usize) {
+        Some(instruction) => println!("=>{:4}  {}", ip, instruction),
+        None => println!("=>{:4}  (end of program)", ip),
+    }
+}
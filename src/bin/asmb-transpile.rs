@@ -0,0 +1,19 @@
+use advent_of_code_2016::computer::codegen::Language;
+use advent_of_code_2016::computer::Computer;
+
+const USAGE: &str = "usage: asmb-transpile <rust|c> <program.txt>";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let language = match args.next().as_deref() {
+        Some("rust") => Language::Rust,
+        Some("c") => Language::C,
+        _ => return Err(USAGE.into()),
+    };
+    let path = args.next().ok_or(USAGE)?;
+    let input = std::fs::read_to_string(path)?;
+
+    print!("{}", Computer::new(&input)?.transpile(language));
+
+    Ok(())
+}
@@ -71,7 +71,7 @@ impl Floor {
         self.0.is_empty()
     }
 
-    pub fn iter(&self) -> std::collections::btree_set::Iter<Equipment> {
+    pub fn iter(&self) -> std::collections::btree_set::Iter<'_, Equipment> {
         self.0.iter()
     }
 
@@ -7,14 +7,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut computer = Computer::new(&input)?;
 
     // After executing the assembunny code in your puzzle input, what value is left in register a?
-    let register_a = computer.run()?.registers()[0];
+    let register_a = computer.run_compiled()?.registers()[0];
     println!("Part 1: {}", register_a);
 
     // If you instead initialize register c to be 1, what value is now left in register a?
     let register_a = computer
         .reset()
         .set_registers([0, 0, 1, 0])
-        .run()?
+        .run_compiled()?
         .registers()[0];
     println!("Part 2: {}", register_a);
 
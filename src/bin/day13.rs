@@ -13,10 +13,10 @@ impl Position {
 
     pub fn neighbouring_positions(self) -> impl Iterator<Item = Self> {
         let xs = once(self.0 + 1)
-            .chain(self.0.checked_sub(1).into_iter())
+            .chain(self.0.checked_sub(1))
             .map(move |x| Position(x, self.1));
         let ys = once(self.1 + 1)
-            .chain(self.1.checked_sub(1).into_iter())
+            .chain(self.1.checked_sub(1))
             .map(move |y| Position(self.0, y));
 
         xs.chain(ys)
@@ -38,7 +38,9 @@ fn is_open_space(position: Position) -> bool {
     let x = position.0;
     let y = position.1;
 
-    (x * (x + 3 + 2 * y) + y * (1 + y) + INPUT).count_ones() % 2 == 0
+    (x * (x + 3 + 2 * y) + y * (1 + y) + INPUT)
+        .count_ones()
+        .is_multiple_of(2)
 }
 
 fn part1(start_position: Position, end_position: Position) -> Option<u64> {
@@ -6,7 +6,6 @@ const INPUT: &[u8] = b"ihaygndm";
 #[derive(Debug, Clone)]
 struct PotentialKey {
     pub nonce: u32,
-    pub hash: [u8; 16],
     pub first_triplet: Option<u8>,
     pub quintuplets: Vec<u8>,
 }
@@ -18,7 +17,6 @@ impl PotentialKey {
 
         PotentialKey {
             nonce,
-            hash,
             first_triplet,
             quintuplets,
         }
@@ -33,7 +31,6 @@ impl PotentialKey {
 
         PotentialKey {
             nonce,
-            hash,
             first_triplet,
             quintuplets,
         }
@@ -56,7 +53,7 @@ impl PotentialKey {
         match value {
             0..=9 => 48 + value,
             10..=15 => 97 + value - 10,
-            _ => unreachable!(value),
+            _ => unreachable!("{}", value),
         }
     }
 
@@ -100,9 +97,9 @@ fn main() {
 /// However, not all of these MD5 hashes are keys, and you need 64 new keys for your one-time pad.
 /// A hash is a key only if:
 /// * It contains three of the same character in a row, like 777. Only consider the first such
-/// triplet in a hash.
+///   triplet in a hash.
 /// * One of the next 1000 hashes in the stream contains that same character five times in a row,
-/// like 77777.
+///   like 77777.
 ///
 /// Considering future hashes for five-of-a-kind sequences does not cause those hashes to be
 /// skipped; instead, regardless of whether the current hash is a key, always resume testing for
@@ -31,7 +31,7 @@ fn checksum(data: &[bool]) -> String {
         .map(|chunk| !(chunk[0] ^ chunk[1]))
         .collect();
 
-    while checksum.len() % 2 == 0 {
+    while checksum.len().is_multiple_of(2) {
         checksum = checksum
             .chunks_exact(2)
             .map(|chunk| !(chunk[0] ^ chunk[1]))
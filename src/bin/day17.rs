@@ -103,7 +103,7 @@ fn part2(start_position: Position, end_position: Position) -> usize {
 }
 
 fn open_directions(steps: &[u8]) -> Vec<u8> {
-    md5::compute(&[INPUT, steps].concat())
+    md5::compute([INPUT, steps].concat())
         .0
         .iter()
         .take(2)
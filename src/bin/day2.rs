@@ -20,12 +20,13 @@ impl From<char> for Direction {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 enum Key {
     One,
     Two,
     Three,
     Four,
+    #[default]
     Five,
     Six,
     Seven,
@@ -94,12 +95,6 @@ impl Key {
     }
 }
 
-impl Default for Key {
-    fn default() -> Self {
-        Key::Five
-    }
-}
-
 impl From<Key> for char {
     fn from(key: Key) -> Self {
         match key {
@@ -116,12 +111,13 @@ impl From<Key> for char {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 enum Key2 {
     One,
     Two,
     Three,
     Four,
+    #[default]
     Five,
     Six,
     Seven,
@@ -218,12 +214,6 @@ impl Key2 {
     }
 }
 
-impl Default for Key2 {
-    fn default() -> Self {
-        Key2::Five
-    }
-}
-
 impl From<Key2> for char {
     fn from(key: Key2) -> Self {
         match key {
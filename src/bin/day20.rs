@@ -54,7 +54,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     });
 
     // In case the last filter doesn't cover all the way to the last address.
-    let allowed_addresses = count + (std::u32::MAX - final_address);
+    let allowed_addresses = count + (u32::MAX - final_address);
 
     println!("Part 2: {}", allowed_addresses);
 
@@ -17,7 +17,7 @@ pub enum Instruction {
 impl<T: AsRef<str>> From<T> for Instruction {
     fn from(instruction: T) -> Self {
         let instruction = instruction.as_ref();
-        let mut iter = instruction.trim().split_whitespace();
+        let mut iter = instruction.split_whitespace();
 
         match iter.next() {
             Some("swap") => match iter.next() {
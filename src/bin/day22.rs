@@ -20,10 +20,10 @@ impl Position {
 
     pub fn neighbouring_positions(self) -> impl Iterator<Item = Self> {
         let xs = once(self.0 + 1)
-            .chain(self.0.checked_sub(1).into_iter())
+            .chain(self.0.checked_sub(1))
             .map(move |x| Position(x, self.1));
         let ys = once(self.1 + 1)
-            .chain(self.1.checked_sub(1).into_iter())
+            .chain(self.1.checked_sub(1))
             .map(move |y| Position(self.0, y));
 
         xs.chain(ys)
@@ -55,7 +55,7 @@ impl Cluster {
         self.0.get(position)
     }
 
-    pub fn iter(&self) -> std::collections::hash_map::Iter<Position, Node> {
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, Position, Node> {
         self.0.iter()
     }
 }
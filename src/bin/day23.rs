@@ -10,7 +10,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // What value should be sent to the safe?
     let register_a = Computer::new(&input)?
         .set_registers([7, 0, 0, 0])
-        .run()?
+        .run_compiled()?
         .registers()[0];
     println!("Part 1: {}", register_a);
 
@@ -18,8 +18,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // the painting again. As it turns out, colored eggs are still eggs. Now you count 12.
     // Anyway, what value should actually be sent to the safe?
     let register_a = Computer::new(&input)?
+        .optimize()
         .set_registers([12, 0, 0, 0])
-        .run()?
+        .run_compiled()?
         .registers()[0];
     println!("Part 2: {}", register_a);
 
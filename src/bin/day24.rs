@@ -42,7 +42,7 @@ impl From<char> for Tile {
 pub struct HVACSystem(Vec<Vec<Tile>>);
 
 impl HVACSystem {
-    pub fn iter(&self) -> std::slice::Iter<Vec<Tile>> {
+    pub fn iter(&self) -> std::slice::Iter<'_, Vec<Tile>> {
         self.0.iter()
     }
 
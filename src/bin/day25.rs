@@ -0,0 +1,54 @@
+use advent_of_code_2016::computer::{Computer, ComputerError};
+
+const INPUT_PATH: &str = "inputs/day25.txt";
+/// Loop detection is costly, so only enable it once the signal looks right for this long.
+const SCREENING_LENGTH: usize = 16;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let input = std::fs::read_to_string(INPUT_PATH)?;
+    let computer = Computer::new(&input)?;
+
+    // What is the lowest positive integer that can be used to initialize register a and cause the
+    // code to output a clock signal of 0, 1, 0, 1... repeating forever?
+    println!("Part 1: {}", part1(&computer)?);
+
+    Ok(())
+}
+
+fn part1(computer: &Computer) -> Result<i32, Box<dyn std::error::Error>> {
+    for a in 1.. {
+        if is_clock_signal(computer.clone().set_registers([a, 0, 0, 0]))? {
+            return Ok(a);
+        }
+    }
+
+    unreachable!()
+}
+
+/// A clock signal alternates between 0 and 1, starting with 0, forever. We check each value as it
+/// is transmitted, until the computer loops back to a previous state: from then on, it will repeat
+/// the same output, which keeps alternating if it has an even (and non-zero) length.
+fn is_clock_signal(computer: &mut Computer) -> Result<bool, Box<dyn std::error::Error>> {
+    computer.optimize();
+
+    for (n, expected) in (1..).zip([0, 1].iter().cycle()) {
+        if n == SCREENING_LENGTH {
+            computer.detect_loops();
+        }
+
+        match computer.run_until_output(n) {
+            Ok(computer) => {
+                if computer.output().get(n - 1) != Some(expected) {
+                    return Ok(false);
+                }
+            }
+            Err(ComputerError::InfiniteLoop { output_start, .. }) => {
+                let period = computer.output().len() - output_start;
+                return Ok(period > 0 && period.is_multiple_of(2));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    unreachable!()
+}
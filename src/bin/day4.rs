@@ -1,5 +1,4 @@
-use advent_of_code_2016::counter::Counter;
-use std::cmp::Reverse;
+use advent_of_code_2016::counter::{ArrayCounter, TieBreak};
 
 const INPUT_PATH: &str = "inputs/day4.txt";
 
@@ -12,9 +11,7 @@ struct Room {
 
 impl From<&str> for Room {
     fn from(input: &str) -> Self {
-        let mut iter = input
-            .trim_end_matches(']')
-            .rsplitn(3, |c| c == '[' || c == '-');
+        let mut iter = input.trim_end_matches(']').rsplitn(3, ['[', '-']);
 
         let checksum = iter.next().unwrap().to_string();
         let sector_id = iter.next().unwrap().parse().unwrap();
@@ -37,20 +34,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let real_rooms: Vec<Room> = rooms
         .into_iter()
         .filter(|room| {
-            let mut sorted_name: Vec<(char, usize)> = room
+            let checksum: String = room
                 .name
                 .chars()
-                .collect::<Counter<char>>()
+                .filter(|&c| c != '-')
+                .collect::<ArrayCounter<char>>()
+                .set_tie_break(TieBreak::key())
+                .most_common_n(room.checksum.len())
                 .into_iter()
-                .filter(|(c, _)| *c != '-')
+                .map(|(&c, _)| c)
                 .collect();
-            sorted_name.sort_by_key(|(c, n)| (Reverse(*n), *c));
-
-            let checksum = sorted_name
-                .iter()
-                .take(room.checksum.len())
-                .map(|(c, _)| c)
-                .collect::<String>();
 
             checksum == room.checksum
         })
@@ -59,7 +59,7 @@ impl From<&str> for IPv7 {
         let mut supernet_sequences = Vec::new();
         let mut hypernet_sequences = Vec::new();
 
-        for (idx, s) in address.split(|c| c == '[' || c == ']').enumerate() {
+        for (idx, s) in address.split(['[', ']']).enumerate() {
             if !s.is_empty() {
                 if idx % 2 == 0 {
                     supernet_sequences.push(s.to_string());
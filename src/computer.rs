@@ -1,17 +1,106 @@
+use instruction_set::InstructionSet;
+use std::collections::{HashMap, VecDeque};
 use std::convert::TryFrom;
 
+pub mod analysis;
+mod assembler;
+mod bytecode;
+pub mod codegen;
+pub mod instruction_set;
+mod ports;
+pub mod search;
+pub mod symbolic;
+
 #[derive(Debug, Clone)]
 pub enum ComputerError {
     InvalidOpCode(String),
     InvalidValue(String),
     InvalidRegister(String),
     MissingArgument(String),
-    InvalidInstructionPointer(i32),
+    /// A token following the last argument of an instruction.
+    UnexpectedArgument(String),
+    InvalidInstructionPointer(i128),
+    /// The result of the instruction at this address does not fit, and the overflow policy is
+    /// `OverflowPolicy::Error`.
+    Overflow(i32),
+    StepLimitExceeded(usize),
+    /// The computer is back in a state it has already been in, so it will run forever. The output
+    /// from `output()[output_start..]` onwards repeats endlessly.
+    InfiniteLoop {
+        instruction_pointer: i32,
+        output_start: usize,
+    },
+    /// Every error found while parsing a program.
+    Parse(Vec<ParseError>),
+    InvalidSnapshot(String),
+    /// The program cannot be evaluated symbolically past this instruction.
+    NotSymbolic {
+        instruction_pointer: i32,
+        reason: &'static str,
+    },
+    /// A name used as a value is neither a label nor a constant.
+    UndefinedSymbol(String),
+    DuplicateSymbol(String),
+    InvalidSymbol(String),
+    /// The `in` instruction at this address needs a value, and none will ever come.
+    NoInput(i32),
 }
 
 impl std::fmt::Display for ComputerError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        write!(f, "{:?}", self)
+        match self {
+            ComputerError::InvalidOpCode(token) if token.is_empty() => write!(f, "missing opcode"),
+            ComputerError::InvalidOpCode(token) => write!(f, "invalid opcode `{}`", token),
+            ComputerError::InvalidValue(token) => write!(f, "invalid value `{}`", token),
+            ComputerError::InvalidRegister(token) => write!(f, "invalid register `{}`", token),
+            ComputerError::MissingArgument(token) => write!(f, "missing argument for `{}`", token),
+            ComputerError::UnexpectedArgument(token) => {
+                write!(f, "unexpected argument `{}`", token)
+            }
+            ComputerError::InvalidInstructionPointer(ip) => {
+                write!(f, "invalid instruction pointer {}", ip)
+            }
+            ComputerError::Overflow(ip) => write!(f, "overflow at instruction {}", ip),
+            ComputerError::StepLimitExceeded(steps) => {
+                write!(f, "program still running after {} steps", steps)
+            }
+            ComputerError::InfiniteLoop {
+                instruction_pointer,
+                ..
+            } => write!(
+                f,
+                "infinite loop detected at instruction {}",
+                instruction_pointer
+            ),
+            ComputerError::InvalidSnapshot(line) => write!(f, "invalid snapshot line `{}`", line),
+            ComputerError::NotSymbolic {
+                instruction_pointer,
+                reason,
+            } => write!(
+                f,
+                "cannot evaluate instruction {} symbolically: {}",
+                instruction_pointer, reason
+            ),
+            ComputerError::UndefinedSymbol(name) => {
+                write!(f, "undefined label or constant `{}`", name)
+            }
+            ComputerError::DuplicateSymbol(name) => {
+                write!(f, "label or constant `{}` defined twice", name)
+            }
+            ComputerError::InvalidSymbol(name) => {
+                write!(f, "invalid label or constant name `{}`", name)
+            }
+            ComputerError::NoInput(ip) => write!(f, "no input left for instruction {}", ip),
+            ComputerError::Parse(errors) => {
+                for (idx, error) in errors.iter().enumerate() {
+                    if idx > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", error)?;
+                }
+                Ok(())
+            }
+        }
     }
 }
 
@@ -19,43 +108,220 @@ impl std::error::Error for ComputerError {}
 
 type ComputerResult<T> = std::result::Result<T, ComputerError>;
 
-#[derive(Debug, Clone, Copy)]
-struct Value(i32);
+/// An error found while parsing a program, along with the (1-based) position of the offending
+/// token. For a missing argument, that is the end of the line.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub error: ComputerError,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.error
+        )
+    }
+}
+
+/// Registers that exist unless specified otherwise (see `Computer::with_registers`).
+const DEFAULT_REGISTERS: &str = "abcd";
+
+/// Width of the values held in registers. Whatever the width, they are stored as `i128`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Width {
+    I32,
+    I64,
+    I128,
+}
+
+impl Width {
+    fn min(self) -> i128 {
+        match self {
+            Width::I32 => i32::MIN.into(),
+            Width::I64 => i64::MIN.into(),
+            Width::I128 => i128::MIN,
+        }
+    }
+
+    fn max(self) -> i128 {
+        match self {
+            Width::I32 => i32::MAX.into(),
+            Width::I64 => i64::MAX.into(),
+            Width::I128 => i128::MAX,
+        }
+    }
+
+    fn wrap(self, value: i128) -> i128 {
+        match self {
+            Width::I32 => (value as i32).into(),
+            Width::I64 => (value as i64).into(),
+            Width::I128 => value,
+        }
+    }
+}
+
+impl std::fmt::Display for Width {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Width::I32 => write!(f, "i32"),
+            Width::I64 => write!(f, "i64"),
+            Width::I128 => write!(f, "i128"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Width {
+    type Error = ComputerError;
+
+    fn try_from(value: &str) -> ComputerResult<Width> {
+        match value {
+            "i32" => Ok(Width::I32),
+            "i64" => Ok(Width::I64),
+            "i128" => Ok(Width::I128),
+            _ => Err(ComputerError::InvalidValue(value.to_string())),
+        }
+    }
+}
+
+/// What to do when the result of an instruction does not fit in the register's `Width`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    Wrap,
+    Saturate,
+    /// Stop with `ComputerError::Overflow`.
+    Error,
+}
+
+impl OverflowPolicy {
+    /// Apply the policy to the result of the instruction at `instruction_pointer`: `exact` is
+    /// `None` if it does not even fit in an `i128`.
+    fn fit(
+        self,
+        width: Width,
+        instruction_pointer: i32,
+        exact: Option<i128>,
+        wrapped: i128,
+        saturated: i128,
+    ) -> ComputerResult<i128> {
+        let (min, max) = (width.min(), width.max());
+        match exact {
+            Some(v) if min <= v && v <= max => Ok(v),
+            _ => match self {
+                OverflowPolicy::Wrap => Ok(width.wrap(wrapped)),
+                OverflowPolicy::Saturate => Ok(saturated.clamp(min, max)),
+                OverflowPolicy::Error => Err(ComputerError::Overflow(instruction_pointer)),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for OverflowPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            OverflowPolicy::Wrap => write!(f, "wrap"),
+            OverflowPolicy::Saturate => write!(f, "saturate"),
+            OverflowPolicy::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl TryFrom<&str> for OverflowPolicy {
+    type Error = ComputerError;
+
+    fn try_from(value: &str) -> ComputerResult<OverflowPolicy> {
+        match value {
+            "wrap" => Ok(OverflowPolicy::Wrap),
+            "saturate" => Ok(OverflowPolicy::Saturate),
+            "error" => Ok(OverflowPolicy::Error),
+            _ => Err(ComputerError::InvalidValue(value.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Value(pub i128);
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", self.0)
+    }
+}
 
 impl TryFrom<&str> for Value {
     type Error = ComputerError;
 
     fn try_from(value: &str) -> ComputerResult<Value> {
-        match value.parse::<i32>() {
+        match value.parse::<i128>() {
             Ok(v) => Ok(Value(v)),
             Err(_) => Err(ComputerError::InvalidValue(value.to_string())),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Register(usize);
+/// A register, named by a lowercase letter: `Register::new(0)` is `a`, `Register::new(25)` is `z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Register(usize);
+
+impl Register {
+    /// The register at `index`, from 0 for `a` to 25 for `z`.
+    pub fn new(index: usize) -> ComputerResult<Register> {
+        if index < 26 {
+            Ok(Register(index))
+        } else {
+            Err(ComputerError::InvalidRegister(index.to_string()))
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl std::fmt::Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", char::from(b'a' + self.0 as u8))
+    }
+}
 
 impl TryFrom<&str> for Register {
     type Error = ComputerError;
 
     fn try_from(register: &str) -> ComputerResult<Register> {
-        match register {
-            "a" => Ok(Register(0)),
-            "b" => Ok(Register(1)),
-            "c" => Ok(Register(2)),
-            "d" => Ok(Register(3)),
+        match register.as_bytes() {
+            &[c] if c.is_ascii_lowercase() => Ok(Register(usize::from(c - b'a'))),
             _ => Err(ComputerError::InvalidRegister(register.to_string())),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Argument {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Argument {
     Value(Value),
     Register(Register),
 }
 
+impl Argument {
+    fn register(self) -> Option<Register> {
+        match self {
+            Argument::Register(r) => Some(r),
+            Argument::Value(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Argument {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            Argument::Value(v) => v.fmt(f),
+            Argument::Register(r) => r.fmt(f),
+        }
+    }
+}
+
 impl<'a> TryFrom<&'a str> for Argument {
     type Error = ComputerError;
 
@@ -68,23 +334,40 @@ impl<'a> TryFrom<&'a str> for Argument {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Instruction {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Instruction {
     Copy(Argument, Argument),
     Increment(Argument),
     Decrement(Argument),
     JumpIfNotZero(Argument, Argument),
     Toggle(Argument),
+    Out(Argument),
+    In(Argument),
 }
 
 impl Instruction {
-    fn toggle(self) -> Self {
+    fn arguments(self) -> Vec<Argument> {
+        match self {
+            Instruction::Copy(a, b) | Instruction::JumpIfNotZero(a, b) => vec![a, b],
+            Instruction::Increment(a)
+            | Instruction::Decrement(a)
+            | Instruction::Toggle(a)
+            | Instruction::Out(a)
+            | Instruction::In(a) => vec![a],
+        }
+    }
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         match self {
-            Instruction::Copy(a, b) => Instruction::JumpIfNotZero(a, b),
-            Instruction::Increment(a) => Instruction::Decrement(a),
-            Instruction::Decrement(a) => Instruction::Increment(a),
-            Instruction::JumpIfNotZero(a, b) => Instruction::Copy(a, b),
-            Instruction::Toggle(a) => Instruction::Increment(a),
+            Instruction::Copy(a, b) => write!(f, "cpy {} {}", a, b),
+            Instruction::Increment(a) => write!(f, "inc {}", a),
+            Instruction::Decrement(a) => write!(f, "dec {}", a),
+            Instruction::JumpIfNotZero(a, b) => write!(f, "jnz {} {}", a, b),
+            Instruction::Toggle(a) => write!(f, "tgl {}", a),
+            Instruction::Out(a) => write!(f, "out {}", a),
+            Instruction::In(a) => write!(f, "in {}", a),
         }
     }
 }
@@ -93,179 +376,1041 @@ impl<'a> TryFrom<&'a str> for Instruction {
     type Error = ComputerError;
 
     fn try_from(instruction: &'a str) -> ComputerResult<Instruction> {
-        let mut iter = instruction.split_whitespace();
-        match iter.next() {
-            Some("cpy") => {
-                let first = iter
-                    .next()
-                    .ok_or_else(|| ComputerError::MissingArgument(instruction.to_string()))
-                    .and_then(Argument::try_from)?;
-                let second = iter
-                    .next()
-                    .ok_or_else(|| ComputerError::MissingArgument(instruction.to_string()))
-                    .and_then(Argument::try_from)?;
-                Ok(Instruction::Copy(first, second))
+        let registers = parse_registers(DEFAULT_REGISTERS)?;
+        instruction_set::parse(instruction, &registers, &|_| None)
+            .map_err(|mut errors| errors.swap_remove(0).1)
+    }
+}
+
+/// Assembunny source for a program, one instruction per line, as returned by `Computer::listing`.
+/// Parsing it gives back the same program.
+#[derive(Debug, Clone, Copy)]
+pub struct Listing<'a> {
+    program: &'a [Instruction],
+    annotated: bool,
+}
+
+impl<'a> Listing<'a> {
+    /// Add a comment with the absolute target of every jump with a constant offset.
+    pub fn annotated(self) -> Self {
+        Listing {
+            annotated: true,
+            ..self
+        }
+    }
+}
+
+impl<'a> std::fmt::Display for Listing<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        for (idx, instruction) in self.program.iter().enumerate() {
+            match instruction {
+                Instruction::JumpIfNotZero(_, Argument::Value(Value(offset))) if self.annotated => {
+                    writeln!(
+                        f,
+                        "{}  ; -> {}",
+                        instruction,
+                        offset.saturating_add(idx as i128)
+                    )?
+                }
+                _ => writeln!(f, "{}", instruction)?,
             }
-            Some("inc") => {
-                let first = iter
-                    .next()
-                    .ok_or_else(|| ComputerError::MissingArgument(instruction.to_string()))
-                    .and_then(Argument::try_from)?;
-                Ok(Instruction::Increment(first))
+        }
+
+        Ok(())
+    }
+}
+
+/// Synthetic instructions standing in for a whole loop of the original program. They are never
+/// parsed nor toggled; `Computer::optimize` overlays them on the program instead.
+#[derive(Debug, Clone, Copy)]
+enum OptimizedInstruction {
+    /// `inc a`, `dec b`, `jnz b -2` (in either order): add `b` to `a`, then clear `b`.
+    Add { target: Register, source: Register },
+    /// `cpy x c`, an `Add` from `c` into `a`, `dec d`, `jnz d -5`: add `x * d` to `a`, then clear
+    /// `c` and `d`.
+    Multiply {
+        target: Register,
+        factor: Argument,
+        counter: Register,
+        outer_counter: Register,
+    },
+}
+
+impl OptimizedInstruction {
+    /// Number of original instructions covered.
+    fn len(self) -> i32 {
+        match self {
+            OptimizedInstruction::Add { .. } => 3,
+            OptimizedInstruction::Multiply { .. } => 6,
+        }
+    }
+
+    /// Recognize an optimizable loop at the start of `program`.
+    fn recognize(program: &[Instruction]) -> Option<Self> {
+        match program {
+            [Instruction::Copy(factor, counter), rest @ ..] => {
+                Self::recognize_multiply(*factor, counter.register()?, rest)
             }
-            Some("dec") => {
-                let first = iter
-                    .next()
-                    .ok_or_else(|| ComputerError::MissingArgument(instruction.to_string()))
-                    .and_then(Argument::try_from)?;
-                Ok(Instruction::Decrement(first))
+            [first, second, Instruction::JumpIfNotZero(test, Argument::Value(Value(-2))), ..] => {
+                let (target, source) = match (first, second) {
+                    (Instruction::Increment(t), Instruction::Decrement(s))
+                    | (Instruction::Decrement(s), Instruction::Increment(t)) => {
+                        (t.register()?, s.register()?)
+                    }
+                    _ => return None,
+                };
+
+                if source.0 == test.register()?.0 && source.0 != target.0 {
+                    Some(OptimizedInstruction::Add { target, source })
+                } else {
+                    None
+                }
             }
-            Some("jnz") => {
-                let first = iter
-                    .next()
-                    .ok_or_else(|| ComputerError::MissingArgument(instruction.to_string()))
-                    .and_then(Argument::try_from)?;
-                let second = iter
-                    .next()
-                    .ok_or_else(|| ComputerError::MissingArgument(instruction.to_string()))
-                    .and_then(Argument::try_from)?;
-                Ok(Instruction::JumpIfNotZero(first, second))
+            _ => None,
+        }
+    }
+
+    /// Recognize the rest of a multiplication loop, following its initial `cpy factor counter`.
+    fn recognize_multiply(
+        factor: Argument,
+        counter: Register,
+        program: &[Instruction],
+    ) -> Option<Self> {
+        let (target, source) = match Self::recognize(program)? {
+            OptimizedInstruction::Add { target, source } => (target, source),
+            _ => return None,
+        };
+
+        let outer_counter = match program.get(3..5)? {
+            [Instruction::Decrement(d), Instruction::JumpIfNotZero(test, offset)] => {
+                let d = d.register()?;
+                match offset {
+                    Argument::Value(Value(-5)) if d.0 == test.register()?.0 => d,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        // The factor must stay constant throughout the loop.
+        let clobbered = [target.0, counter.0, outer_counter.0];
+        if source.0 != counter.0
+            || clobbered[..2].contains(&outer_counter.0)
+            || factor.register().is_some_and(|r| clobbered.contains(&r.0))
+        {
+            return None;
+        }
+
+        Some(OptimizedInstruction::Multiply {
+            target,
+            factor,
+            counter,
+            outer_counter,
+        })
+    }
+}
+
+/// Recognizes an optimizable loop at the start of a program, like `OptimizedInstruction::recognize`.
+type LoopRecognizer<I> = fn(&[I]) -> Option<OptimizedInstruction>;
+
+/// What happened during a call to `Machine::step`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step<I = Instruction> {
+    /// The instruction at `instruction_pointer` was executed.
+    Executed {
+        instruction_pointer: i32,
+        instruction: I,
+    },
+    /// The optimized loop starting at `instruction_pointer` was executed in one go.
+    Optimized { instruction_pointer: i32 },
+    /// The instruction at `instruction_pointer` reads input (like `in`) and no value is available
+    /// yet, so nothing was executed.
+    Blocked { instruction_pointer: i32 },
+    /// The instruction pointer is outside the program, so nothing was executed.
+    Halted,
+}
+
+/// Why `Computer::resume` returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// An `out` instruction transmitted this value.
+    Output(i128),
+    /// The next instruction is an `in` with no value available yet.
+    Blocked,
+    Halted,
+}
+
+/// A step recorded by a computer with tracing enabled (see `Computer::record_trace`). Registers
+/// are listed in the order they were declared in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry<I = Instruction> {
+    pub step: Step<I>,
+    pub registers_before: Vec<i128>,
+    pub registers_after: Vec<i128>,
+}
+
+impl<I: std::fmt::Display> std::fmt::Display for TraceEntry<I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let (instruction_pointer, instruction) = match self.step {
+            Step::Executed {
+                instruction_pointer,
+                ref instruction,
+            } => (instruction_pointer, instruction.to_string()),
+            Step::Optimized {
+                instruction_pointer,
+            } => (instruction_pointer, String::from("(optimized loop)")),
+            Step::Blocked {
+                instruction_pointer,
+            } => (instruction_pointer, String::from("(waiting for input)")),
+            Step::Halted => return write!(f, "halted"),
+        };
+
+        write!(
+            f,
+            "{:4}  {:<16}  {:?} -> {:?}",
+            instruction_pointer, instruction, self.registers_before, self.registers_after
+        )
+    }
+}
+
+/// Report of the most executed instructions, as returned by `Computer::hot_spots`.
+#[derive(Debug, Clone)]
+pub struct HotSpots<'a, I = Instruction> {
+    program: &'a [I],
+    counts: &'a [usize],
+    n: usize,
+}
+
+impl<'a, I: std::fmt::Display> std::fmt::Display for HotSpots<'a, I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let total: usize = self.counts.iter().sum();
+        let mut indices: Vec<usize> = (0..self.counts.len())
+            .filter(|&idx| self.counts[idx] > 0)
+            .collect();
+        indices.sort_by_key(|&idx| (std::cmp::Reverse(self.counts[idx]), idx));
+
+        for idx in indices.into_iter().take(self.n) {
+            writeln!(
+                f,
+                "{:12} {:6.2}%  {:4}  {}",
+                self.counts[idx],
+                100.0 * self.counts[idx] as f64 / total as f64,
+                idx,
+                self.program[idx]
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Conditions on which `Computer::run_to_breakpoint` stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stop before executing the instruction at this address.
+    InstructionPointer(i32),
+    /// Stop after a step that sets the register to this value.
+    RegisterEquals(Register, i128),
+    /// Stop after a step that changes the register's value.
+    RegisterChanged(Register),
+}
+
+/// The full state of a computer, as returned by `Computer::snapshot`.
+///
+/// Its text form is stable, and meant to be saved to disk or diffed:
+///
+/// ```text
+/// registers a=1 b=0 c=0 d=0
+/// width i32
+/// overflow error
+/// ip 2
+/// output 0 1
+/// program
+/// cpy a b
+/// ...
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Snapshot {
+    pub registers: Vec<(Register, i128)>,
+    pub width: Width,
+    pub overflow_policy: OverflowPolicy,
+    pub instruction_pointer: i32,
+    pub output: Vec<i128>,
+    pub program: Vec<Instruction>,
+}
+
+impl std::fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "registers")?;
+        for (register, value) in &self.registers {
+            write!(f, " {}={}", register, value)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "width {}", self.width)?;
+        writeln!(f, "overflow {}", self.overflow_policy)?;
+        writeln!(f, "ip {}", self.instruction_pointer)?;
+        write!(f, "output")?;
+        for value in &self.output {
+            write!(f, " {}", value)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "program")?;
+        write!(
+            f,
+            "{}",
+            Listing {
+                program: &self.program,
+                annotated: false,
             }
-            Some("tgl") => {
-                let first = iter
-                    .next()
-                    .ok_or_else(|| ComputerError::MissingArgument(instruction.to_string()))
-                    .and_then(Argument::try_from)?;
-                Ok(Instruction::Toggle(first))
+        )
+    }
+}
+
+impl std::str::FromStr for Snapshot {
+    type Err = ComputerError;
+
+    fn from_str(s: &str) -> ComputerResult<Snapshot> {
+        let mut lines = s.lines();
+        let mut header = |key: &str| -> ComputerResult<(String, Vec<&str>)> {
+            let line = lines.next().unwrap_or_default();
+            let mut tokens = line.split_whitespace();
+            if tokens.next() == Some(key) {
+                Ok((line.to_string(), tokens.collect()))
+            } else {
+                Err(ComputerError::InvalidSnapshot(line.to_string()))
             }
-            _ => unreachable!(),
+        };
+
+        let (line, tokens) = header("registers")?;
+        let registers = tokens
+            .into_iter()
+            .map(|token| {
+                let mut pair = token.splitn(2, '=');
+                let register = pair.next().and_then(|r| Register::try_from(r).ok());
+                let value = pair.next().and_then(|v| v.parse().ok());
+                register.zip(value)
+            })
+            .collect::<Option<Vec<(Register, i128)>>>()
+            .ok_or(ComputerError::InvalidSnapshot(line))?;
+
+        let (line, tokens) = header("width")?;
+        let width = match tokens.as_slice() {
+            [width] => Width::try_from(*width).ok(),
+            _ => None,
+        }
+        .ok_or(ComputerError::InvalidSnapshot(line))?;
+
+        let (line, tokens) = header("overflow")?;
+        let overflow_policy = match tokens.as_slice() {
+            [policy] => OverflowPolicy::try_from(*policy).ok(),
+            _ => None,
+        }
+        .ok_or(ComputerError::InvalidSnapshot(line))?;
+
+        let (line, tokens) = header("ip")?;
+        let instruction_pointer = match tokens.as_slice() {
+            [ip] => ip.parse().ok(),
+            _ => None,
+        }
+        .ok_or(ComputerError::InvalidSnapshot(line))?;
+
+        let (line, tokens) = header("output")?;
+        let output = tokens
+            .into_iter()
+            .map(|token| token.parse().ok())
+            .collect::<Option<Vec<i128>>>()
+            .ok_or(ComputerError::InvalidSnapshot(line))?;
+
+        let (line, tokens) = header("program")?;
+        if !tokens.is_empty() {
+            return Err(ComputerError::InvalidSnapshot(line));
         }
+
+        // The program starts on the seventh line, and may use any register.
+        let all_registers: Vec<Register> = (0..26).map(Register).collect();
+        let program =
+            assembler::assemble(&lines.collect::<Vec<_>>().join("\n"), 7, &all_registers)?;
+
+        Ok(Snapshot {
+            registers,
+            width,
+            overflow_policy,
+            instruction_pointer,
+            output,
+            program,
+        })
     }
 }
 
+/// Parse a list of register names, such as "abcd".
+fn parse_registers(registers: &str) -> ComputerResult<Vec<Register>> {
+    let mut result = Vec::new();
+    for c in registers.chars() {
+        let register = Register::try_from(c.encode_utf8(&mut [0; 4]) as &str)?;
+        if !result.contains(&register) {
+            result.push(register);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Remembers the states a computer has been through, for exact loop detection.
 #[derive(Debug, Clone)]
-pub struct Computer {
-    registers: [Value; 4],
+struct LoopDetector<I> {
+    /// Every version of the program seen so far, since `tgl` can modify it.
+    programs: Vec<Vec<I>>,
+    /// Index of the current version of the program in `programs`.
+    program_id: usize,
+    /// Program version, instruction pointer and registers of every state visited, along with the
+    /// length of the output at the time.
+    states: HashMap<(usize, i32, Vec<Value>), usize>,
+}
+
+impl<I> Default for LoopDetector<I> {
+    fn default() -> Self {
+        LoopDetector {
+            programs: Vec::new(),
+            program_id: 0,
+            states: HashMap::new(),
+        }
+    }
+}
+
+impl<I: InstructionSet> LoopDetector<I> {
+    fn program_changed(&mut self, program: &[I]) {
+        self.program_id = match self.programs.iter().position(|p| p == program) {
+            Some(id) => id,
+            None => {
+                self.programs.push(program.to_vec());
+                self.programs.len() - 1
+            }
+        };
+    }
+}
+
+/// A register machine running any `InstructionSet` (see `instruction_set`).
+#[derive(Debug, Clone)]
+pub struct Machine<I> {
+    /// Indexed by `Register`, up to the last declared register.
+    registers: Vec<Value>,
+    register_names: Vec<Register>,
+    width: Width,
+    overflow_policy: OverflowPolicy,
     instruction_pointer: i32,
-    program: Vec<Instruction>,
+    program: Vec<I>,
+    original_program: Vec<I>,
+    /// Values for `in` instructions, taken before those from the input port.
+    input: VecDeque<i128>,
+    output: Vec<i128>,
+    ports: ports::Ports,
+    /// How to recognize optimizable loops, if optimizations are enabled (see `Computer::optimize`).
+    recognize_loop: Option<LoopRecognizer<I>>,
+    optimizations: Vec<Option<OptimizedInstruction>>,
+    breakpoints: Vec<Breakpoint>,
+    /// `None` unless loop detection is enabled.
+    loop_detector: Option<LoopDetector<I>>,
+    /// The last `trace_capacity` steps (none if tracing is disabled).
+    trace: VecDeque<TraceEntry<I>>,
+    trace_capacity: usize,
+    /// Number of steps executed at each address, if profiling is enabled.
+    execution_counts: Option<Vec<usize>>,
 }
 
-impl Computer {
+/// The assembunny computer: a `Machine` running `Instruction`, with tooling of its own such as
+/// loop optimizations, snapshots, static analysis and code generation.
+pub type Computer = Machine<Instruction>;
+
+impl<I: InstructionSet> Machine<I> {
+    /// Parse a program, one instruction per line. On failure, every error found is returned at
+    /// once, as a `ComputerError::Parse`.
+    ///
+    /// Besides plain puzzle input, this accepts blank lines, `;` comments, `name:` labels and
+    /// `.equ NAME value` constants. Labels are lowered to the relative offset of their address, so
+    /// `jnz a loop` jumps to `loop`.
+    ///
+    /// The computer has registers `a` through `d`, holding `i32` values, and stops with an error on
+    /// overflow.
     pub fn new(program: &str) -> ComputerResult<Self> {
-        let program: Vec<Instruction> = program
-            .lines()
-            .map(Instruction::try_from)
-            .collect::<ComputerResult<Vec<Instruction>>>()?;
+        Machine::with_registers(program, DEFAULT_REGISTERS)
+    }
+
+    /// Parse a program for a computer with the given registers, e.g. "abcd" or "xyz". Their order
+    /// is the one used by `registers` and `set_registers`.
+    pub fn with_registers(program: &str, registers: &str) -> ComputerResult<Self> {
+        let register_names = parse_registers(registers)?;
+        let program = assembler::assemble(program, 1, &register_names)?;
+        let len = register_names.iter().map(|r| r.0 + 1).max().unwrap_or(0);
 
-        Ok(Computer {
-            registers: [Value(0); 4],
+        Ok(Machine {
+            registers: vec![Value(0); len],
+            register_names,
+            width: Width::I32,
+            overflow_policy: OverflowPolicy::Error,
             instruction_pointer: 0,
+            original_program: program.clone(),
             program,
+            input: VecDeque::new(),
+            output: Vec::new(),
+            ports: ports::Ports::default(),
+            recognize_loop: None,
+            optimizations: Vec::new(),
+            breakpoints: Vec::new(),
+            loop_detector: None,
+            trace: VecDeque::new(),
+            trace_capacity: 0,
+            execution_counts: None,
         })
     }
 
+    /// Clear the registers, the instruction pointer, the queued input and the output, and undo any
+    /// `tgl` instruction. Ports, breakpoints, optimizations, loop detection, tracing and profiling
+    /// stay enabled, but the trace and execution counts start over.
     pub fn reset(&mut self) -> &mut Self {
-        self.registers = [Value(0); 4];
+        self.registers.iter_mut().for_each(|r| *r = Value(0));
         self.instruction_pointer = 0;
+        self.input.clear();
+        self.output.clear();
+        self.program.clone_from(&self.original_program);
+        self.recognize_loops();
+        if let Some(loop_detector) = &mut self.loop_detector {
+            *loop_detector = LoopDetector::default();
+            loop_detector.program_changed(&self.program);
+        }
+        self.trace.clear();
+        if let Some(counts) = &mut self.execution_counts {
+            counts.iter_mut().for_each(|count| *count = 0);
+        }
         self
     }
 
-    pub fn set_registers(&mut self, registers: [i32; 4]) -> &mut Self {
-        for (a, b) in registers.iter().zip(self.registers.iter_mut()) {
-            *b = Value(*a);
+    /// Set the registers, in the order they were declared in. Values are taken as is, even if they
+    /// do not fit in the current `Width`.
+    pub fn set_registers<T, R>(&mut self, registers: R) -> &mut Self
+    where
+        T: Into<i128>,
+        R: IntoIterator<Item = T>,
+    {
+        for (value, register) in registers.into_iter().zip(&self.register_names) {
+            self.registers[register.0] = Value(value.into());
         }
         self
     }
 
-    pub fn run(&mut self) -> ComputerResult<&mut Self> {
-        while let Some(&i) = self.program.get(self.instruction_pointer as usize) {
-            match i {
-                Instruction::Copy(a, b) => self.copy_instruction(a, b),
-                Instruction::Increment(a) => self.increment_instruction(a),
-                Instruction::Decrement(a) => self.decrement_instruction(a),
-                Instruction::JumpIfNotZero(a, b) => self.jump_if_not_zero_instruction(a, b)?,
-                Instruction::Toggle(a) => self.toggle_instruction(a),
-            }
+    pub fn set_register<T: Into<i128>>(
+        &mut self,
+        register: Register,
+        value: T,
+    ) -> ComputerResult<&mut Self> {
+        if !self.register_names.contains(&register) {
+            return Err(ComputerError::InvalidRegister(register.to_string()));
         }
 
+        self.registers[register.0] = Value(value.into());
         Ok(self)
     }
 
-    fn copy_instruction(&mut self, a: Argument, b: Argument) {
-        let a = match a {
-            Argument::Register(Register(r)) => self.registers[r],
-            Argument::Value(v) => v,
-        };
+    pub fn set_width(&mut self, width: Width) -> &mut Self {
+        self.width = width;
+        self
+    }
+
+    pub fn set_overflow_policy(&mut self, overflow_policy: OverflowPolicy) -> &mut Self {
+        self.overflow_policy = overflow_policy;
+        self
+    }
 
-        if let Argument::Register(Register(r)) = b {
-            self.registers[r] = a;
+    /// Recognize optimizable loops in the program as it currently stands, if optimizations are
+    /// enabled.
+    fn recognize_loops(&mut self) {
+        if let Some(recognize) = self.recognize_loop {
+            self.optimizations = (0..self.program.len())
+                .map(|idx| recognize(&self.program[idx..]))
+                .collect();
         }
+    }
+
+    /// Before every step, check whether the computer has already been in the same state
+    /// (instruction pointer, registers and program). If so, it is stuck in an infinite loop, and
+    /// stepping returns `ComputerError::InfiniteLoop`.
+    ///
+    /// This is exact, but remembers every state visited, so it is best combined with `optimize`.
+    pub fn detect_loops(&mut self) -> &mut Self {
+        if self.loop_detector.is_none() {
+            let mut loop_detector = LoopDetector::default();
+            loop_detector.program_changed(&self.program);
+            self.loop_detector = Some(loop_detector);
+        }
+        self
+    }
+
+    /// Keep a record of the last `capacity` steps (see `trace`). A capacity of 0 disables tracing.
+    pub fn record_trace(&mut self, capacity: usize) -> &mut Self {
+        self.trace_capacity = capacity;
+        while self.trace.len() > capacity {
+            self.trace.pop_front();
+        }
+        self
+    }
+
+    /// Count the number of steps executed at each address (see `execution_counts` and
+    /// `hot_spots`). An optimized loop counts as a single step at its first address.
+    pub fn profile(&mut self) -> &mut Self {
+        if self.execution_counts.is_none() {
+            self.execution_counts = Some(vec![0; self.program.len()]);
+        }
+        self
+    }
+
+    /// Run the program until it halts. An `in` instruction with no value available waits for one
+    /// from the input port, or fails with `ComputerError::NoInput` if there is none.
+    pub fn run(&mut self) -> ComputerResult<&mut Self> {
+        while self.step_or_wait()? != Step::Halted {}
 
-        self.instruction_pointer += 1;
+        Ok(self)
     }
 
-    fn increment_instruction(&mut self, a: Argument) {
-        if let Argument::Register(Register(r)) = a {
-            self.registers[r].0 += 1;
+    /// Run the program for at most `max_steps` steps (an optimized loop counting as a single step).
+    /// Returns `ComputerError::StepLimitExceeded` if it has not halted by then.
+    pub fn run_with_limit(&mut self, max_steps: usize) -> ComputerResult<&mut Self> {
+        for _ in 0..max_steps {
+            if self.step_or_wait()? == Step::Halted {
+                return Ok(self);
+            }
+        }
+
+        if self.is_halted() {
+            Ok(self)
+        } else {
+            Err(ComputerError::StepLimitExceeded(max_steps))
         }
+    }
+
+    /// Run the program until `predicate` holds (it is checked before every step), or until it
+    /// halts, whichever comes first.
+    pub fn run_until<P: FnMut(&Self) -> bool>(
+        &mut self,
+        mut predicate: P,
+    ) -> ComputerResult<&mut Self> {
+        while !predicate(self) && self.step_or_wait()? != Step::Halted {}
+
+        Ok(self)
+    }
 
-        self.instruction_pointer += 1;
+    /// Run the program until it has transmitted at least `n` values (see `output`), or until it
+    /// halts, whichever comes first.
+    pub fn run_until_output(&mut self, n: usize) -> ComputerResult<&mut Self> {
+        self.run_until(|computer| computer.output.len() >= n)
     }
 
-    fn decrement_instruction(&mut self, a: Argument) {
-        if let Argument::Register(Register(r)) = a {
-            self.registers[r].0 -= 1;
+    /// Run the program until one of the breakpoints is hit, returning it, or until it halts.
+    ///
+    /// At least one instruction is executed, so that calling this again after a breakpoint
+    /// resumes execution. Breakpoints inside an optimized loop are skipped over.
+    pub fn run_to_breakpoint(&mut self) -> ComputerResult<Option<Breakpoint>> {
+        loop {
+            let before = self.registers.clone();
+            if self.step_or_wait()? == Step::Halted {
+                return Ok(None);
+            }
+
+            let hit = self.breakpoints.iter().find(|&&b| match b {
+                Breakpoint::InstructionPointer(ip) => ip == self.instruction_pointer,
+                Breakpoint::RegisterEquals(Register(r), v) => {
+                    before.get(r) != Some(&Value(v)) && self.registers.get(r) == Some(&Value(v))
+                }
+                Breakpoint::RegisterChanged(Register(r)) => before.get(r) != self.registers.get(r),
+            });
+
+            if let Some(&b) = hit {
+                return Ok(Some(b));
+            }
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> &mut Self {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
         }
+        self
+    }
+
+    pub fn remove_breakpoint(&mut self, breakpoint: Breakpoint) -> &mut Self {
+        self.breakpoints.retain(|&b| b != breakpoint);
+        self
+    }
+
+    pub fn clear_breakpoints(&mut self) -> &mut Self {
+        self.breakpoints.clear();
+        self
+    }
 
-        self.instruction_pointer += 1;
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
     }
 
-    fn jump_if_not_zero_instruction(
+    /// Run the program until it halts, writing every step to `writer`.
+    pub fn run_traced<W: std::io::Write>(
         &mut self,
-        a: Argument,
-        b: Argument,
-    ) -> Result<(), ComputerError> {
-        let a = match a {
-            Argument::Value(Value(v)) => v,
-            Argument::Register(Register(r)) => self.registers[r].0,
+        mut writer: W,
+    ) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        loop {
+            let registers_before = self.registers();
+            let step = self.step_or_wait()?;
+            if step == Step::Halted {
+                return Ok(self);
+            }
+
+            let entry = TraceEntry {
+                step,
+                registers_before,
+                registers_after: self.registers(),
+            };
+            writeln!(writer, "{}", entry)?;
+        }
+    }
+
+    /// Execute the instruction at the instruction pointer (or the optimized loop starting there).
+    pub fn step(&mut self) -> ComputerResult<Step<I>> {
+        let registers_before = if self.trace_capacity > 0 {
+            self.registers()
+        } else {
+            Vec::new()
         };
+        let step = self.execute_step()?;
 
-        let b = match b {
-            Argument::Value(Value(v)) => v,
-            Argument::Register(Register(r)) => self.registers[r].0,
+        let instruction_pointer = match step {
+            Step::Executed {
+                instruction_pointer,
+                ..
+            }
+            | Step::Optimized {
+                instruction_pointer,
+            } => instruction_pointer,
+            Step::Blocked { .. } | Step::Halted => return Ok(step),
         };
 
-        if a != 0 {
-            self.instruction_pointer += b;
-            if self.instruction_pointer < 0 {
-                return Err(ComputerError::InvalidInstructionPointer(
-                    self.instruction_pointer,
-                ));
+        if let Some(counts) = &mut self.execution_counts {
+            counts[instruction_pointer as usize] += 1;
+        }
+
+        if self.trace_capacity > 0 {
+            if self.trace.len() == self.trace_capacity {
+                self.trace.pop_front();
             }
-        } else {
-            self.instruction_pointer += 1;
+            self.trace.push_back(TraceEntry {
+                step,
+                registers_before,
+                registers_after: self.registers(),
+            });
         }
 
-        Ok(())
+        Ok(step)
     }
 
-    fn toggle_instruction(&mut self, a: Argument) {
-        let a = match a {
-            Argument::Value(Value(b)) => b,
-            Argument::Register(Register(r)) => self.registers[r].0,
+    fn execute_step(&mut self) -> ComputerResult<Step<I>> {
+        let instruction_pointer = self.instruction_pointer;
+        let i = match self.program.get(instruction_pointer as usize) {
+            Some(&i) => i,
+            None => return Ok(Step::Halted),
         };
 
-        let target = a + (self.instruction_pointer as i32);
-        if target >= 0 {
-            if let Some(i) = self.program.get_mut(target as usize) {
-                *i = i.toggle();
+        if i.reads_input() && !self.poll_input() {
+            return Ok(Step::Blocked {
+                instruction_pointer,
+            });
+        }
+
+        if let Some(loop_detector) = &mut self.loop_detector {
+            let state = (
+                loop_detector.program_id,
+                instruction_pointer,
+                self.registers.clone(),
+            );
+            if let Some(&output_start) = loop_detector.states.get(&state) {
+                return Err(ComputerError::InfiniteLoop {
+                    instruction_pointer,
+                    output_start,
+                });
             }
+            loop_detector.states.insert(state, self.output.len());
         }
 
-        self.instruction_pointer += 1;
+        if let Some(&Some(o)) = self.optimizations.get(self.instruction_pointer as usize) {
+            if self.optimized_instruction(o)? {
+                return Ok(Step::Optimized {
+                    instruction_pointer,
+                });
+            }
+        }
+
+        i.execute(self)?;
+
+        Ok(Step::Executed {
+            instruction_pointer,
+            instruction: i,
+        })
+    }
+
+    /// Execute a synthetic instruction, if the loop it replaces would terminate normally. Returns
+    /// whether it was executed.
+    fn optimized_instruction(&mut self, o: OptimizedInstruction) -> ComputerResult<bool> {
+        match o {
+            OptimizedInstruction::Add {
+                target: Register(t),
+                source: Register(s),
+            } => {
+                let (target, source) = (self.registers[t].0, self.registers[s].0);
+                if source <= 0 {
+                    return Ok(false);
+                }
+
+                let sum = match self.loop_result(
+                    &[target, source],
+                    target.checked_add(source),
+                    target.wrapping_add(source),
+                    target.saturating_add(source),
+                ) {
+                    Some(sum) => sum,
+                    None => return Ok(false),
+                };
+                self.registers[t] = Value(sum);
+                self.registers[s].0 = 0;
+            }
+            OptimizedInstruction::Multiply {
+                target: Register(t),
+                factor,
+                counter: Register(c),
+                outer_counter: Register(d),
+            } => {
+                let factor = match factor {
+                    Argument::Value(Value(v)) => v,
+                    Argument::Register(Register(r)) => self.registers[r].0,
+                };
+                let (target, times) = (self.registers[t].0, self.registers[d].0);
+                if factor <= 0 || times <= 0 {
+                    return Ok(false);
+                }
+
+                // Stepping through a product that does not even fit in an `i128` is hopeless
+                // anyway, so there is no need for its wrapped or saturated value.
+                let exact = match factor
+                    .checked_mul(times)
+                    .and_then(|product| product.checked_add(target))
+                {
+                    Some(exact) => exact,
+                    None => return Ok(false),
+                };
+                let sum =
+                    match self.loop_result(&[target, factor, times], Some(exact), exact, exact) {
+                        Some(sum) => sum,
+                        None => return Ok(false),
+                    };
+                self.registers[t] = Value(sum);
+                self.registers[c].0 = 0;
+                self.registers[d].0 = 0;
+            }
+        }
+
+        self.instruction_pointer += o.len();
+        Ok(true)
+    }
+
+    /// The overflow policy applied once to the final result of an optimized loop, which only
+    /// matches applying it at every step if the `operands` of the loop fit in the width. `None`
+    /// means the loop has to run step by step: some operand does not fit, or the result does not
+    /// fit and the policy is `OverflowPolicy::Error`, whose error must point at the instruction
+    /// that overflows.
+    fn loop_result(
+        &self,
+        operands: &[i128],
+        exact: Option<i128>,
+        wrapped: i128,
+        saturated: i128,
+    ) -> Option<i128> {
+        let (min, max) = (self.width.min(), self.width.max());
+        if operands.iter().any(|v| !(min..=max).contains(v)) {
+            return None;
+        }
+
+        self.overflow_policy
+            .fit(
+                self.width,
+                self.instruction_pointer,
+                exact,
+                wrapped,
+                saturated,
+            )
+            .ok()
+    }
+
+    /// Values of the registers, in the order they were declared in.
+    pub fn registers(&self) -> Vec<i128> {
+        self.register_names
+            .iter()
+            .map(|r| self.registers[r.0].0)
+            .collect()
+    }
+
+    pub fn named_registers(&self) -> Vec<(Register, i128)> {
+        self.register_names
+            .iter()
+            .map(|&r| (r, self.registers[r.0].0))
+            .collect()
+    }
+
+    /// Value of a register, or 0 if it was not declared.
+    pub fn register(&self, register: Register) -> i128 {
+        self.registers.get(register.0).map_or(0, |v| v.0)
+    }
+
+    pub fn register_names(&self) -> &[Register] {
+        &self.register_names
     }
 
-    pub fn registers(&self) -> Vec<i32> {
-        self.registers.iter().map(|r| r.0).collect()
+    pub fn width(&self) -> Width {
+        self.width
+    }
+
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
+    /// Whether the instruction pointer is outside the program.
+    pub fn is_halted(&self) -> bool {
+        self.program
+            .get(self.instruction_pointer as usize)
+            .is_none()
+    }
+
+    pub fn instruction_pointer(&self) -> i32 {
+        self.instruction_pointer
+    }
+
+    /// The program as it currently stands, including the effects of any `tgl` instruction.
+    pub fn program(&self) -> &[I] {
+        &self.program
+    }
+
+    /// The steps recorded since tracing was enabled (or the last `reset`), oldest first.
+    pub fn trace(&self) -> impl Iterator<Item = &TraceEntry<I>> {
+        self.trace.iter()
+    }
+
+    /// Number of steps executed at each address, if profiling is enabled.
+    pub fn execution_counts(&self) -> Option<&[usize]> {
+        self.execution_counts.as_deref()
+    }
+
+    /// The `n` most executed instructions, if profiling is enabled.
+    pub fn hot_spots(&self, n: usize) -> Option<HotSpots<'_, I>> {
+        self.execution_counts.as_ref().map(|counts| HotSpots {
+            program: &self.program,
+            counts,
+            n,
+        })
+    }
+
+    /// Values transmitted by `out` instructions since the last `reset`, oldest first.
+    pub fn output(&self) -> &[i128] {
+        &self.output
+    }
+}
+
+impl Computer {
+    /// Replace the common addition and multiplication loops with equivalent synthetic
+    /// instructions. The original program is kept, so toggling an instruction simply triggers a new
+    /// pass, and a loop whose preconditions are not met (e.g. a non-positive counter) still runs
+    /// instruction by instruction.
+    pub fn optimize(&mut self) -> &mut Self {
+        self.recognize_loop = Some(OptimizedInstruction::recognize);
+        self.recognize_loops();
+        self
+    }
+
+    /// Capture the registers, instruction pointer, output and (possibly toggled) program.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            registers: self.named_registers(),
+            width: self.width,
+            overflow_policy: self.overflow_policy,
+            instruction_pointer: self.instruction_pointer,
+            output: self.output.clone(),
+            program: self.program.clone(),
+        }
+    }
+
+    /// Bring the computer back to the state captured by `snapshot`, including its width and
+    /// overflow policy. Other settings (breakpoints, optimizations, ...) are kept, and `reset` still
+    /// goes back to the program as first loaded.
+    ///
+    /// Fails, leaving the computer untouched, if the snapshot uses registers that this computer
+    /// does not have, or holds values that do not fit the snapshot's width.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> ComputerResult<&mut Self> {
+        let undeclared = snapshot
+            .registers
+            .iter()
+            .map(|&(register, _)| register)
+            .chain(
+                snapshot
+                    .program
+                    .iter()
+                    .flat_map(|i| i.arguments())
+                    .filter_map(Argument::register),
+            )
+            .find(|r| !self.register_names.contains(r));
+        if let Some(register) = undeclared {
+            return Err(ComputerError::InvalidRegister(register.to_string()));
+        }
+
+        let (min, max) = (snapshot.width.min(), snapshot.width.max());
+        let out_of_range = snapshot
+            .registers
+            .iter()
+            .map(|&(_, value)| value)
+            .find(|value| !(min..=max).contains(value));
+        if let Some(value) = out_of_range {
+            return Err(ComputerError::InvalidValue(value.to_string()));
+        }
+
+        for &(register, value) in &snapshot.registers {
+            self.registers[register.0] = Value(value);
+        }
+        self.width = snapshot.width;
+        self.overflow_policy = snapshot.overflow_policy;
+        self.instruction_pointer = snapshot.instruction_pointer;
+        self.output.clone_from(&snapshot.output);
+        self.program.clone_from(&snapshot.program);
+        self.recognize_loops();
+        if let Some(loop_detector) = &mut self.loop_detector {
+            loop_detector.program_changed(&self.program);
+        }
+        if let Some(counts) = &mut self.execution_counts {
+            counts.resize(self.program.len(), 0);
+        }
+        Ok(self)
+    }
+
+    /// Static analysis of the program as it currently stands.
+    pub fn analyze(&self) -> analysis::Analysis {
+        analysis::Analysis::new(&self.program)
+    }
+
+    /// Search for initial values of `register` among `values` that make the program behave a
+    /// certain way (see `search::Search`). The other registers keep their current values.
+    pub fn search(
+        &self,
+        register: Register,
+        values: std::ops::Range<i128>,
+    ) -> ComputerResult<search::Search<'_>> {
+        search::Search::new(self, register, values)
+    }
+
+    /// The source of the program as it currently stands.
+    pub fn listing(&self) -> Listing<'_> {
+        Listing {
+            program: &self.program,
+            annotated: false,
+        }
     }
 }
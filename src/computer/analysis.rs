@@ -0,0 +1,545 @@
+//! Static analysis of assembunny programs: control-flow graph, basic blocks, loops, register
+//! liveness and dead code.
+//!
+//! `tgl` can rewrite any instruction it may reach, so the analysis is conservative: an instruction
+//! that may be toggled is assumed to behave like any of its variants, and a jump whose offset comes
+//! from a register may go anywhere.
+
+use super::instruction_set::InstructionSet;
+use super::{Argument, Instruction, Register, Value};
+use std::convert::TryFrom;
+
+/// Registers as a bit set, `a` being the lowest bit.
+type Registers = u32;
+
+fn mask(argument: Argument) -> Registers {
+    match argument {
+        Argument::Register(Register(r)) => 1 << r,
+        Argument::Value(_) => 0,
+    }
+}
+
+fn registers(mask: Registers) -> Vec<Register> {
+    (0..26)
+        .filter(|r| mask & (1 << r) != 0)
+        .map(Register)
+        .collect()
+}
+
+/// Registers read by an instruction.
+fn uses(instruction: Instruction) -> Registers {
+    match instruction {
+        Instruction::Copy(a, _) => mask(a),
+        Instruction::JumpIfNotZero(a, b) => mask(a) | mask(b),
+        Instruction::Increment(a)
+        | Instruction::Decrement(a)
+        | Instruction::Toggle(a)
+        | Instruction::Out(a) => mask(a),
+        Instruction::In(_) => 0,
+    }
+}
+
+/// Registers written by an instruction.
+fn defs(instruction: Instruction) -> Registers {
+    match instruction {
+        Instruction::Copy(_, b) => mask(b),
+        Instruction::Increment(a) | Instruction::Decrement(a) | Instruction::In(a) => mask(a),
+        Instruction::JumpIfNotZero(..) | Instruction::Toggle(_) | Instruction::Out(_) => 0,
+    }
+}
+
+/// Whether an instruction does more than reading and writing registers, or jumping: `in` consumes
+/// a value even if the register it writes is never read.
+fn has_side_effects(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Toggle(_) | Instruction::Out(_) | Instruction::In(_)
+    )
+}
+
+/// Every form an instruction can take, by toggling it any number of times.
+fn variants(instruction: Instruction) -> Vec<Instruction> {
+    let mut variants = vec![instruction];
+    let mut current = instruction.toggle();
+    while !variants.contains(&current) {
+        variants.push(current);
+        current = current.toggle();
+    }
+
+    variants
+}
+
+/// Where control may go after an instruction.
+#[derive(Debug, Clone, Default)]
+struct Flow {
+    /// Addresses inside the program.
+    successors: Vec<usize>,
+    /// Whether the program may halt, by going outside of the program.
+    exits: bool,
+    /// Whether the target is computed from a register.
+    dynamic: bool,
+}
+
+impl Flow {
+    fn new(idx: usize, instruction: Instruction, len: usize) -> Self {
+        let idx = idx as i128;
+        let mut targets = Vec::new();
+        let mut dynamic = false;
+
+        match instruction {
+            Instruction::JumpIfNotZero(condition, offset) => {
+                if condition != Argument::Value(Value(0)) {
+                    match offset {
+                        Argument::Value(Value(offset)) => targets.push(idx.saturating_add(offset)),
+                        Argument::Register(_) => dynamic = true,
+                    }
+                }
+                if let Argument::Register(_) | Argument::Value(Value(0)) = condition {
+                    targets.push(idx + 1);
+                }
+            }
+            _ => targets.push(idx + 1),
+        }
+
+        let mut flow = Flow {
+            dynamic,
+            ..Flow::default()
+        };
+        for target in targets {
+            match usize::try_from(target) {
+                Ok(target) if target < len => flow.successors.push(target),
+                _ => flow.exits = true,
+            }
+        }
+
+        flow.successors.sort_unstable();
+        flow.successors.dedup();
+        flow
+    }
+
+    fn merge(&mut self, other: Flow) {
+        for successor in other.successors {
+            if !self.successors.contains(&successor) {
+                self.successors.push(successor);
+            }
+        }
+        self.successors.sort_unstable();
+        self.exits |= other.exits;
+        self.dynamic |= other.dynamic;
+    }
+}
+
+/// A maximal sequence of instructions `start..end` that can only be entered at `start`, and only
+/// left after `end - 1`. Jumps with a dynamic target are not taken into account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub end: usize,
+    /// Indices of the blocks control may go to after this one.
+    pub successors: Vec<usize>,
+    /// Whether the program may halt after this block.
+    pub exits: bool,
+    /// Whether this block ends with a jump whose target is computed from a register.
+    pub dynamic: bool,
+}
+
+/// A natural loop: `header` is the only way in, and `blocks` (including the header) are the
+/// blocks that can go back to it without leaving the loop. Both are indices of blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    pub header: usize,
+    pub blocks: Vec<usize>,
+}
+
+/// Result of the analysis of a program, see `Computer::analyze`.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    program: Vec<Instruction>,
+    flows: Vec<Flow>,
+    toggle_targets: Vec<bool>,
+    reachable: Vec<bool>,
+    blocks: Vec<BasicBlock>,
+    block_of: Vec<usize>,
+    loops: Vec<Loop>,
+    live_in: Vec<Registers>,
+    live_out: Vec<Registers>,
+}
+
+impl Analysis {
+    pub fn new(program: &[Instruction]) -> Self {
+        let len = program.len();
+
+        // Any `tgl` may run, whether it looks reachable or not: toggling can create new jumps.
+        let mut toggle_targets = vec![false; len];
+        for (idx, &instruction) in program.iter().enumerate() {
+            match instruction {
+                Instruction::Toggle(Argument::Value(Value(offset))) => {
+                    if let Ok(target) = usize::try_from(offset.saturating_add(idx as i128)) {
+                        if target < len {
+                            toggle_targets[target] = true;
+                        }
+                    }
+                }
+                Instruction::Toggle(Argument::Register(_)) => {
+                    toggle_targets.iter_mut().for_each(|t| *t = true);
+                }
+                _ => (),
+            }
+        }
+
+        let flows: Vec<Flow> = program
+            .iter()
+            .enumerate()
+            .map(|(idx, &instruction)| {
+                let mut flow = Flow::new(idx, instruction, len);
+                if toggle_targets[idx] {
+                    for variant in variants(instruction).into_iter().skip(1) {
+                        flow.merge(Flow::new(idx, variant, len));
+                    }
+                }
+                flow
+            })
+            .collect();
+
+        let reachable = Self::find_reachable(&flows);
+        let (blocks, block_of) = Self::find_blocks(&flows);
+        let loops = Self::find_loops(&blocks);
+
+        let mut analysis = Analysis {
+            program: program.to_vec(),
+            flows,
+            toggle_targets,
+            reachable,
+            blocks,
+            block_of,
+            loops,
+            live_in: vec![0; len],
+            live_out: vec![0; len],
+        };
+        analysis.liveness();
+        analysis
+    }
+
+    fn find_reachable(flows: &[Flow]) -> Vec<bool> {
+        let mut reachable = vec![false; flows.len()];
+        let mut stack = vec![0];
+        while let Some(idx) = stack.pop() {
+            if idx >= flows.len() || reachable[idx] {
+                continue;
+            }
+
+            reachable[idx] = true;
+            if flows[idx].dynamic {
+                // A dynamic jump could land anywhere.
+                return vec![true; flows.len()];
+            }
+            stack.extend(&flows[idx].successors);
+        }
+
+        reachable
+    }
+
+    fn find_blocks(flows: &[Flow]) -> (Vec<BasicBlock>, Vec<usize>) {
+        let len = flows.len();
+        let mut leaders = vec![false; len];
+        if len > 0 {
+            leaders[0] = true;
+        }
+        for (idx, flow) in flows.iter().enumerate() {
+            if flow.successors != [idx + 1] || flow.exits || flow.dynamic {
+                for &successor in &flow.successors {
+                    leaders[successor] = true;
+                }
+                if idx + 1 < len {
+                    leaders[idx + 1] = true;
+                }
+            }
+        }
+
+        let starts: Vec<usize> = (0..len).filter(|&idx| leaders[idx]).collect();
+        let mut block_of = vec![0; len];
+        for (block, &start) in starts.iter().enumerate() {
+            let end = starts.get(block + 1).copied().unwrap_or(len);
+            block_of[start..end].iter_mut().for_each(|b| *b = block);
+        }
+
+        let blocks = starts
+            .iter()
+            .enumerate()
+            .map(|(block, &start)| {
+                let end = starts.get(block + 1).copied().unwrap_or(len);
+                let last = &flows[end - 1];
+                let mut successors: Vec<usize> =
+                    last.successors.iter().map(|&s| block_of[s]).collect();
+                successors.dedup();
+                BasicBlock {
+                    start,
+                    end,
+                    successors,
+                    exits: last.exits,
+                    dynamic: last.dynamic,
+                }
+            })
+            .collect();
+
+        (blocks, block_of)
+    }
+
+    /// Find the natural loops, from the back edges of a depth-first search from the entry.
+    fn find_loops(blocks: &[BasicBlock]) -> Vec<Loop> {
+        if blocks.is_empty() {
+            return Vec::new();
+        }
+
+        let mut back_edges = Vec::new();
+        let mut visited = vec![false; blocks.len()];
+        let mut on_stack = vec![false; blocks.len()];
+        // Each frame is a block and the index of the next successor to visit.
+        let mut stack = vec![(0, 0)];
+        visited[0] = true;
+        on_stack[0] = true;
+        while let Some(&mut (block, ref mut next)) = stack.last_mut() {
+            match blocks[block].successors.get(*next) {
+                Some(&successor) => {
+                    *next += 1;
+                    if on_stack[successor] {
+                        back_edges.push((block, successor));
+                    } else if !visited[successor] {
+                        visited[successor] = true;
+                        on_stack[successor] = true;
+                        stack.push((successor, 0));
+                    }
+                }
+                None => {
+                    on_stack[block] = false;
+                    stack.pop();
+                }
+            }
+        }
+
+        let mut loops: Vec<Loop> = Vec::new();
+        for (tail, header) in back_edges {
+            let mut body = vec![header];
+            let mut stack = vec![tail];
+            while let Some(block) = stack.pop() {
+                if body.contains(&block) {
+                    continue;
+                }
+                body.push(block);
+                stack.extend(
+                    (0..blocks.len())
+                        .filter(|&p| visited[p] && blocks[p].successors.contains(&block)),
+                );
+            }
+            body.sort_unstable();
+
+            // Loops sharing a header are merged.
+            match loops.iter_mut().find(|l| l.header == header) {
+                Some(l) => {
+                    l.blocks.extend(body);
+                    l.blocks.sort_unstable();
+                    l.blocks.dedup();
+                }
+                None => loops.push(Loop {
+                    header,
+                    blocks: body,
+                }),
+            }
+        }
+
+        loops.sort_by_key(|l| l.header);
+        loops
+    }
+
+    /// Backward dataflow analysis. Every register is live when the program halts, since that is
+    /// how it returns its result, and after a dynamic jump, since it could go anywhere.
+    fn liveness(&mut self) {
+        let all = self
+            .program
+            .iter()
+            .flat_map(|&i| variants(i))
+            .fold(0, |acc, i| acc | uses(i) | defs(i));
+
+        let (uses, kills): (Vec<Registers>, Vec<Registers>) = self
+            .program
+            .iter()
+            .enumerate()
+            .map(|(idx, &instruction)| {
+                if self.toggle_targets[idx] {
+                    let variants = variants(instruction);
+                    let used = variants.iter().fold(0, |acc, &i| acc | uses(i));
+                    let killed = variants.iter().fold(!0, |acc, &i| acc & defs(i));
+                    (used, killed)
+                } else {
+                    (uses(instruction), defs(instruction))
+                }
+            })
+            .unzip();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for idx in (0..self.program.len()).rev() {
+                let flow = &self.flows[idx];
+                let mut live_out = if flow.exits || flow.dynamic { all } else { 0 };
+                for &successor in &flow.successors {
+                    live_out |= self.live_in[successor];
+                }
+
+                let live_in = uses[idx] | (live_out & !kills[idx]);
+                if live_in != self.live_in[idx] || live_out != self.live_out[idx] {
+                    self.live_in[idx] = live_in;
+                    self.live_out[idx] = live_out;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    pub fn program(&self) -> &[Instruction] {
+        &self.program
+    }
+
+    /// Addresses control may go to after the instruction at `idx` (excluding halting and dynamic
+    /// jumps, see `exits` and `dynamic_jumps`).
+    pub fn successors(&self, idx: usize) -> &[usize] {
+        &self.flows[idx].successors
+    }
+
+    /// Whether the program may halt right after the instruction at `idx`.
+    pub fn exits(&self, idx: usize) -> bool {
+        self.flows[idx].exits
+    }
+
+    pub fn blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+
+    /// Index of the block containing the instruction at `idx`.
+    pub fn block_of(&self, idx: usize) -> usize {
+        self.block_of[idx]
+    }
+
+    pub fn loops(&self) -> &[Loop] {
+        &self.loops
+    }
+
+    /// Registers whose value may be read before being overwritten, from the instruction at `idx`.
+    pub fn live_in(&self, idx: usize) -> Vec<Register> {
+        registers(self.live_in[idx])
+    }
+
+    /// Registers whose value may be read before being overwritten, after the instruction at `idx`.
+    pub fn live_out(&self, idx: usize) -> Vec<Register> {
+        registers(self.live_out[idx])
+    }
+
+    /// Instructions that may be rewritten by a `tgl`.
+    pub fn toggle_targets(&self) -> Vec<usize> {
+        (0..self.program.len())
+            .filter(|&idx| self.toggle_targets[idx])
+            .collect()
+    }
+
+    /// Jumps whose target is not known statically: the offset comes from a register, or the
+    /// instruction may be toggled into or out of a jump.
+    pub fn dynamic_jumps(&self) -> Vec<usize> {
+        (0..self.program.len())
+            .filter(|&idx| {
+                let jumps = |i: &Instruction| matches!(i, Instruction::JumpIfNotZero(..));
+                self.flows[idx].dynamic
+                    || (self.toggle_targets[idx] && variants(self.program[idx]).iter().any(jumps))
+            })
+            .collect()
+    }
+
+    /// Instructions that can never be executed.
+    pub fn unreachable(&self) -> Vec<usize> {
+        (0..self.program.len())
+            .filter(|&idx| !self.reachable[idx])
+            .collect()
+    }
+
+    /// Reachable instructions whose only effect is to write a register that is never read
+    /// afterwards.
+    pub fn dead_stores(&self) -> Vec<usize> {
+        (0..self.program.len())
+            .filter(|&idx| {
+                let instruction = self.program[idx];
+                let written = defs(instruction);
+                self.reachable[idx]
+                    && !self.toggle_targets[idx]
+                    && !has_side_effects(instruction)
+                    && written != 0
+                    && written & self.live_out[idx] == 0
+            })
+            .collect()
+    }
+
+    /// Unreachable instructions and dead stores.
+    pub fn dead_code(&self) -> Vec<usize> {
+        let mut dead = self.unreachable();
+        dead.extend(self.dead_stores());
+        dead.sort_unstable();
+        dead
+    }
+
+    /// The control-flow graph in Graphviz DOT format.
+    pub fn dot(&self) -> Dot<'_> {
+        Dot(self)
+    }
+}
+
+/// Graphviz DOT rendering of the control-flow graph, as returned by `Analysis::dot`.
+#[derive(Debug, Clone, Copy)]
+pub struct Dot<'a>(&'a Analysis);
+
+impl<'a> std::fmt::Display for Dot<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let analysis = self.0;
+        let toggled = analysis.toggle_targets();
+        let dead = analysis.dead_code();
+
+        writeln!(f, "digraph assembunny {{")?;
+        writeln!(f, "    node [shape=box, fontname=monospace];")?;
+        writeln!(f, "    entry [shape=point];")?;
+        writeln!(f, "    exit [shape=doublecircle, label=\"halt\"];")?;
+        if !analysis.blocks.is_empty() {
+            writeln!(f, "    entry -> block0;")?;
+        }
+
+        for (idx, block) in analysis.blocks.iter().enumerate() {
+            let mut label = String::new();
+            for address in block.start..block.end {
+                let marker = if dead.contains(&address) {
+                    " (dead)"
+                } else if toggled.contains(&address) {
+                    " (tgl)"
+                } else {
+                    ""
+                };
+                label.push_str(&format!(
+                    "{:3}  {}{}\\l",
+                    address, analysis.program[address], marker
+                ));
+            }
+
+            let header = analysis.loops.iter().any(|l| l.header == idx);
+            let style = if header { ", style=bold" } else { "" };
+            writeln!(f, "    block{} [label=\"{}\"{}];", idx, label, style)?;
+
+            for successor in &block.successors {
+                writeln!(f, "    block{} -> block{};", idx, successor)?;
+            }
+            if block.exits {
+                writeln!(f, "    block{} -> exit;", idx)?;
+            }
+            if block.dynamic {
+                writeln!(f, "    block{} -> dynamic{};", idx, idx)?;
+                writeln!(f, "    dynamic{} [shape=circle, label=\"?\"];", idx)?;
+            }
+        }
+
+        writeln!(f, "}}")
+    }
+}
@@ -0,0 +1,129 @@
+//! Front-end for an extended assembunny syntax, lowered to plain instructions of any
+//! `InstructionSet`:
+//!
+//! ```text
+//! .equ COUNT 5        ; a named constant
+//!         cpy COUNT a
+//! loop:   dec a       ; a label, for the address of the instruction that follows it
+//!         jnz a loop  ; used as a value, a label is the offset from here to its address
+//! ```
+//!
+//! Blank lines and comments are ignored, so plain puzzle input is valid as is. Labels and
+//! constants may be used before they are defined, but may not look like a register.
+
+use super::instruction_set::{self, InstructionSet};
+use super::{ComputerError, ComputerResult, ParseError, Register, Value};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, Copy)]
+enum Symbol {
+    Label(usize),
+    Constant(i128),
+}
+
+/// Whether `token` is a valid name for a label or a constant: letters, digits and underscores,
+/// not starting with a digit, and not a register name.
+pub(super) fn is_symbol(token: &str) -> bool {
+    let mut chars = token.chars();
+    let first = chars.next();
+    matches!(first, Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && Register::try_from(token).is_err()
+}
+
+fn parse_error(line_number: usize, line: &str, offset: usize, error: ComputerError) -> ParseError {
+    ParseError {
+        line: line_number,
+        column: line[..offset].chars().count() + 1,
+        error,
+    }
+}
+
+/// Parse a program, one instruction per line, numbering lines from `first_line` in errors.
+pub(super) fn assemble<I: InstructionSet>(
+    program: &str,
+    first_line: usize,
+    registers: &[Register],
+) -> ComputerResult<Vec<I>> {
+    let mut errors = Vec::new();
+    let mut symbols = HashMap::new();
+    // Line number, line and byte offset in the line of every instruction.
+    let mut instructions = Vec::new();
+
+    for (idx, line) in program.lines().enumerate() {
+        let line_number = idx + first_line;
+        let offset = |token: &str| token.as_ptr() as usize - line.as_ptr() as usize;
+        let code = line.split(';').next().unwrap_or_default();
+        let mut tokens = code.split_whitespace();
+
+        let mut define = |name: &str, symbol| {
+            if !is_symbol(name) {
+                let error = ComputerError::InvalidSymbol(name.to_string());
+                errors.push(parse_error(line_number, line, offset(name), error));
+            } else if symbols.insert(name.to_string(), symbol).is_some() {
+                let error = ComputerError::DuplicateSymbol(name.to_string());
+                errors.push(parse_error(line_number, line, offset(name), error));
+            }
+        };
+
+        let mut start = match tokens.next() {
+            Some(".equ") => {
+                let name = tokens.next();
+                let value = tokens.next().map(|token| (token, Value::try_from(token)));
+                match (name, value) {
+                    (Some(name), Some((_, Ok(Value(v))))) => define(name, Symbol::Constant(v)),
+                    (Some(_), Some((token, Err(error)))) => {
+                        errors.push(parse_error(line_number, line, offset(token), error));
+                    }
+                    _ => {
+                        let error = ComputerError::MissingArgument(".equ".to_string());
+                        errors.push(parse_error(line_number, line, code.trim_end().len(), error));
+                    }
+                }
+                for token in tokens {
+                    let error = ComputerError::UnexpectedArgument(token.to_string());
+                    errors.push(parse_error(line_number, line, offset(token), error));
+                }
+                continue;
+            }
+            Some(token) => offset(token),
+            None => continue,
+        };
+
+        let first = code[start..].split_whitespace().next().unwrap_or_default();
+        if let Some(name) = first.strip_suffix(':') {
+            define(name, Symbol::Label(instructions.len()));
+            start += first.len();
+            if code[start..].trim().is_empty() {
+                continue;
+            }
+        }
+
+        instructions.push((line_number, line, start));
+    }
+
+    let mut program = Vec::with_capacity(instructions.len());
+    for (address, &(line_number, line, start)) in instructions.iter().enumerate() {
+        let resolve = |name: &str| match symbols.get(name) {
+            Some(&Symbol::Label(target)) => Some(target as i128 - address as i128),
+            Some(&Symbol::Constant(v)) => Some(v),
+            None => None,
+        };
+
+        match instruction_set::parse(&line[start..], registers, &resolve) {
+            Ok(instruction) => program.push(instruction),
+            Err(e) => errors.extend(
+                e.into_iter()
+                    .map(|(offset, error)| parse_error(line_number, line, start + offset, error)),
+            ),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        errors.sort_by_key(|e| (e.line, e.column));
+        Err(ComputerError::Parse(errors))
+    }
+}
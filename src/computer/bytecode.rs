@@ -0,0 +1,146 @@
+//! A faster backend for `Computer`: the program is decoded once into operations whose arguments
+//! are already resolved, so the hot instructions need neither to match on their arguments nor to
+//! go through the overflow policy unless they actually overflow.
+
+use super::instruction_set::{Context, InstructionSet};
+use super::{Argument, Computer, ComputerResult, Instruction, Register, Value, Width};
+use std::convert::TryFrom;
+
+/// A pre-decoded instruction.
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    /// Copy a value, already known to fit in the register, to a register.
+    CopyValue(i128, usize),
+    CopyRegister(usize, usize),
+    Increment(usize),
+    Decrement(usize),
+    /// Jump by a constant offset if the register is not zero.
+    JumpIfRegister(usize, i128),
+    /// Jump by a constant offset.
+    Jump(i128),
+    /// An instruction with no effect but moving on to the next one, such as `cpy 1 2` or
+    /// `jnz 0 a`. Copying a value that does not fit is not one: the overflow policy applies.
+    Skip,
+    /// Anything else (`tgl`, `out`, `in`, jumps by a register, ...) is left to the interpreter.
+    Interpret(Instruction),
+}
+
+impl Op {
+    fn compile(instruction: Instruction, width: Width) -> Op {
+        use Argument::{Register as R, Value as V};
+
+        let fits = |v: i128| width.min() <= v && v <= width.max();
+        match instruction {
+            Instruction::Copy(V(Value(v)), R(Register(r))) if fits(v) => Op::CopyValue(v, r),
+            Instruction::Copy(R(Register(a)), R(Register(b))) => Op::CopyRegister(a, b),
+            Instruction::Copy(V(Value(v)), V(_)) if fits(v) => Op::Skip,
+            Instruction::Copy(R(_), V(_))
+            | Instruction::Increment(V(_))
+            | Instruction::Decrement(V(_)) => Op::Skip,
+            Instruction::Increment(R(Register(r))) => Op::Increment(r),
+            Instruction::Decrement(R(Register(r))) => Op::Decrement(r),
+            Instruction::JumpIfNotZero(V(Value(0)), _) => Op::Skip,
+            Instruction::JumpIfNotZero(V(_), V(Value(offset))) => Op::Jump(offset),
+            Instruction::JumpIfNotZero(R(Register(r)), V(Value(offset))) => {
+                Op::JumpIfRegister(r, offset)
+            }
+            i => Op::Interpret(i),
+        }
+    }
+}
+
+impl Computer {
+    /// Run the program until it halts, like `run`, but with the program compiled to pre-decoded
+    /// operations first. `tgl` re-compiles the instruction it modifies.
+    ///
+    /// Tracing, profiling and loop detection need the interpreter: if any of them is enabled, this
+    /// is the same as `run`.
+    pub fn run_compiled(&mut self) -> ComputerResult<&mut Self> {
+        if self.trace_capacity > 0
+            || self.execution_counts.is_some()
+            || self.loop_detector.is_some()
+        {
+            return self.run();
+        }
+
+        let width = self.width;
+        let max = width.max();
+        let min = width.min();
+        let mut ops: Vec<Op> = self
+            .program
+            .iter()
+            .map(|&i| Op::compile(i, width))
+            .collect();
+
+        while let Some(&op) = ops.get(self.instruction_pointer as usize) {
+            if let Some(&Some(o)) = self.optimizations.get(self.instruction_pointer as usize) {
+                if self.optimized_instruction(o)? {
+                    continue;
+                }
+            }
+
+            match op {
+                Op::CopyValue(v, r) => {
+                    self.registers[r] = Value(v);
+                    self.instruction_pointer += 1;
+                }
+                Op::CopyRegister(a, b) => {
+                    self.registers[b] = self.registers[a];
+                    self.instruction_pointer += 1;
+                }
+                // Registers may hold values that do not fit (see `set_register`), so only a value
+                // known to stay in range skips the overflow policy.
+                Op::Increment(r) => {
+                    let v = self.registers[r].0;
+                    self.registers[r] = if min <= v && v < max {
+                        Value(v + 1)
+                    } else {
+                        Value(self.add(v, 1)?)
+                    };
+                    self.instruction_pointer += 1;
+                }
+                Op::Decrement(r) => {
+                    let v = self.registers[r].0;
+                    self.registers[r] = if min < v && v <= max {
+                        Value(v - 1)
+                    } else {
+                        Value(self.add(v, -1)?)
+                    };
+                    self.instruction_pointer += 1;
+                }
+                Op::JumpIfRegister(r, offset) => {
+                    if self.registers[r].0 != 0 {
+                        self.jump(offset)?;
+                    } else {
+                        self.instruction_pointer += 1;
+                    }
+                }
+                Op::Jump(offset) => self.jump(offset)?,
+                Op::Skip => self.instruction_pointer += 1,
+                Op::Interpret(Instruction::Toggle(a)) => {
+                    let offset = match a {
+                        Argument::Value(Value(v)) => v,
+                        Argument::Register(Register(r)) => self.registers[r].0,
+                    };
+                    let target = offset.saturating_add(self.instruction_pointer.into());
+                    Instruction::Toggle(a).execute(self)?;
+
+                    if let Ok(target) = usize::try_from(target) {
+                        if let Some(&i) = self.program.get(target) {
+                            ops[target] = Op::compile(i, width);
+                        }
+                    }
+                }
+                Op::Interpret(i @ Instruction::In(_)) => {
+                    if !self.poll_input() {
+                        self.wait_for_input()?;
+                    }
+                    i.execute(self)?;
+                }
+                Op::Interpret(i) => i.execute(self)?,
+            }
+        }
+
+        Ok(self)
+    }
+}
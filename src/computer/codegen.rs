@@ -0,0 +1,568 @@
+//! Translation of assembunny programs to standalone Rust or C source, to run them natively.
+//!
+//! The generated program is a loop around a `match` (or `switch`) on the instruction pointer. If
+//! the program uses `tgl`, every instruction that could be toggled is emitted in all of its
+//! variants, and a table tracks which variant each address currently holds.
+
+use super::instruction_set::InstructionSet;
+use super::{Argument, Computer, Instruction, OverflowPolicy, Register, Value, Width};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    /// C with the `__int128` and `__builtin_add_overflow` extensions of GCC and Clang.
+    C,
+}
+
+/// Source of a program translated to another language, as returned by `Computer::transpile`.
+///
+/// The generated program starts from the computer's current state. Its command-line arguments,
+/// if any, replace the values of the registers, in the order they were declared. It reads the
+/// values for `in` from its standard input, and prints every value transmitted by `out` as
+/// `out <value>`, then the registers when it halts.
+#[derive(Debug, Clone)]
+pub struct Source<'a> {
+    computer: &'a Computer,
+    language: Language,
+    /// The variants each instruction goes through when toggled, starting with the current one.
+    variants: Vec<Vec<Instruction>>,
+    /// `next[address][k]` is the index of the variant that `variants[address][k]` toggles to.
+    next: Vec<Vec<usize>>,
+}
+
+impl<'a> Source<'a> {
+    pub(super) fn new(computer: &'a Computer, language: Language) -> Self {
+        let toggles = computer
+            .program
+            .iter()
+            .any(|i| matches!(i, Instruction::Toggle(_)));
+
+        let mut variants = Vec::new();
+        let mut next = Vec::new();
+        for &i in &computer.program {
+            let mut v = vec![i];
+            let mut n = Vec::new();
+            while toggles && n.len() < v.len() {
+                let toggled = v[n.len()].toggle();
+                match v.iter().position(|&other| other == toggled) {
+                    Some(k) => n.push(k),
+                    None => {
+                        n.push(v.len());
+                        v.push(toggled);
+                    }
+                }
+            }
+            variants.push(v);
+            next.push(n);
+        }
+
+        Source {
+            computer,
+            language,
+            variants,
+            next,
+        }
+    }
+
+    fn toggles(&self) -> bool {
+        self.next.iter().any(|n| !n.is_empty())
+    }
+
+    fn inputs(&self) -> bool {
+        self.variants
+            .iter()
+            .flatten()
+            .any(|i| matches!(i, Instruction::In(_)))
+    }
+
+    fn literal(&self, v: i128) -> String {
+        match self.language {
+            Language::Rust => format!("{}", v),
+            // A decimal constant is at most a `long long`, which `-9223372036854775808` is not.
+            Language::C if v > i64::MIN.into() && v <= i64::MAX.into() => format!("{}", v),
+            Language::C => {
+                let bits = v as u128;
+                format!(
+                    "(value_t)(((unsigned __int128)0x{:x}u << 64) | 0x{:x}u)",
+                    bits >> 64,
+                    bits as u64
+                )
+            }
+        }
+    }
+
+    fn argument(&self, a: Argument) -> String {
+        match a {
+            Argument::Value(Value(v)) => self.literal(v),
+            Argument::Register(Register(r)) => format!("registers[{}]", r),
+        }
+    }
+
+    /// Statements executing `i`, each ending with a semicolon or a block.
+    fn statements(&self, i: Instruction) -> Vec<String> {
+        let width = self.computer.width;
+        let rust = self.language == Language::Rust;
+        let mut statements = Vec::new();
+
+        match i {
+            Instruction::Copy(Argument::Value(Value(v)), Argument::Register(b))
+                if v < width.min() || v > width.max() =>
+            {
+                let value = self.literal(v);
+                statements.push(format!("registers[{}] = add(pc, {}, 0);", b.0, value));
+            }
+            Instruction::Copy(a, Argument::Register(b)) => {
+                statements.push(format!("registers[{}] = {};", b.0, self.argument(a)));
+            }
+            Instruction::Increment(Argument::Register(Register(r))) => {
+                statements.push(format!("registers[{0}] = add(pc, registers[{0}], 1);", r));
+            }
+            Instruction::Decrement(Argument::Register(Register(r))) => {
+                statements.push(format!("registers[{0}] = add(pc, registers[{0}], -1);", r));
+            }
+            Instruction::JumpIfNotZero(Argument::Value(Value(0)), _) => (),
+            Instruction::JumpIfNotZero(Argument::Value(_), b) => {
+                return vec![format!("pc = jump(pc, {});", self.argument(b))];
+            }
+            Instruction::JumpIfNotZero(a, b) => {
+                let condition = if rust {
+                    format!("{} != 0", self.argument(a))
+                } else {
+                    format!("({} != 0)", self.argument(a))
+                };
+                return vec![format!(
+                    "if {} {{ pc = jump(pc, {}); }} else {{ pc += 1; }}",
+                    condition,
+                    self.argument(b)
+                )];
+            }
+            Instruction::Toggle(a) => {
+                let variants = if rust { "&mut variants" } else { "variants" };
+                statements.push(format!("toggle({}, pc, {});", variants, self.argument(a)));
+            }
+            Instruction::Out(a) => {
+                let a = self.argument(a);
+                statements.push(if rust {
+                    format!("println!(\"out {{}}\", {});", a)
+                } else {
+                    format!(
+                        "printf(\"out \"); print_value(stdout, {}); printf(\"\\n\");",
+                        a
+                    )
+                });
+            }
+            Instruction::In(Argument::Register(Register(r))) => {
+                statements.push(format!("registers[{}] = input(pc);", r));
+            }
+            Instruction::In(Argument::Value(_)) => statements.push("input(pc);".to_string()),
+            // Instructions with a value where a register is expected do nothing.
+            _ => (),
+        }
+
+        statements.push("pc += 1;".to_string());
+        statements
+    }
+
+    fn write_rust(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let computer = self.computer;
+        let width = computer.width;
+        let variants = self.variants.iter().map(Vec::len).max().unwrap_or(1);
+
+        writeln!(f, "// Generated from an assembunny program.")?;
+        writeln!(f)?;
+        writeln!(f, "#![allow(dead_code)]")?;
+        writeln!(f)?;
+        writeln!(f, "const MIN: i128 = {};", width.min())?;
+        writeln!(f, "const MAX: i128 = {};", width.max())?;
+        writeln!(f, "const LEN: i128 = {};", computer.program.len())?;
+        if self.toggles() {
+            writeln!(
+                f,
+                "const NEXT: [[usize; {}]; {}] = [",
+                variants,
+                self.next.len()
+            )?;
+            for n in &self.next {
+                let row: Vec<String> = (0..variants)
+                    .map(|k| n.get(k).copied().unwrap_or(0).to_string())
+                    .collect();
+                writeln!(f, "    [{}],", row.join(", "))?;
+            }
+            writeln!(f, "];")?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "fn add(pc: i128, a: i128, b: i128) -> i128 {{")?;
+        writeln!(f, "    match a.checked_add(b) {{")?;
+        writeln!(f, "        Some(v) if MIN <= v && v <= MAX => v,")?;
+        match (computer.overflow_policy, width) {
+            (OverflowPolicy::Wrap, Width::I32) => {
+                writeln!(f, "        _ => a.wrapping_add(b) as i32 as i128,")?
+            }
+            (OverflowPolicy::Wrap, Width::I64) => {
+                writeln!(f, "        _ => a.wrapping_add(b) as i64 as i128,")?
+            }
+            (OverflowPolicy::Wrap, Width::I128) => writeln!(f, "        _ => a.wrapping_add(b),")?,
+            (OverflowPolicy::Saturate, _) => {
+                writeln!(f, "        _ => a.saturating_add(b).clamp(MIN, MAX),")?
+            }
+            (OverflowPolicy::Error, _) => {
+                writeln!(f, "        _ => {{")?;
+                writeln!(
+                    f,
+                    "            eprintln!(\"error: overflow at instruction {{}}\", pc);"
+                )?;
+                writeln!(f, "            std::process::exit(1);")?;
+                writeln!(f, "        }}")?;
+            }
+        }
+        writeln!(f, "    }}")?;
+        writeln!(f, "}}")?;
+        writeln!(f)?;
+
+        writeln!(f, "fn jump(pc: i128, offset: i128) -> i128 {{")?;
+        writeln!(f, "    let target = pc.saturating_add(offset);")?;
+        writeln!(f, "    if target < 0 || target > i32::MAX as i128 {{")?;
+        writeln!(
+            f,
+            "        eprintln!(\"error: invalid instruction pointer {{}}\", target);"
+        )?;
+        writeln!(f, "        std::process::exit(1);")?;
+        writeln!(f, "    }}")?;
+        writeln!(f, "    target")?;
+        writeln!(f, "}}")?;
+        writeln!(f)?;
+
+        if self.toggles() {
+            writeln!(
+                f,
+                "fn toggle(variants: &mut [usize], pc: i128, offset: i128) {{"
+            )?;
+            writeln!(f, "    let target = pc.saturating_add(offset);")?;
+            writeln!(f, "    if 0 <= target && target < LEN {{")?;
+            writeln!(f, "        let t = target as usize;")?;
+            writeln!(f, "        variants[t] = NEXT[t][variants[t]];")?;
+            writeln!(f, "    }}")?;
+            writeln!(f, "}}")?;
+            writeln!(f)?;
+        }
+
+        if self.inputs() {
+            writeln!(f, "fn input(pc: i128) -> i128 {{")?;
+            writeln!(f, "    let mut line = String::new();")?;
+            writeln!(
+                f,
+                "    if std::io::stdin().read_line(&mut line).unwrap_or(0) == 0 {{"
+            )?;
+            writeln!(
+                f,
+                "        eprintln!(\"error: no input left for instruction {{}}\", pc);"
+            )?;
+            writeln!(f, "        std::process::exit(1);")?;
+            writeln!(f, "    }}")?;
+            writeln!(f, "    line.trim().parse().expect(\"invalid input\")")?;
+            writeln!(f, "}}")?;
+            writeln!(f)?;
+        }
+
+        let registers: Vec<String> = computer
+            .registers
+            .iter()
+            .map(|&Value(v)| self.literal(v))
+            .collect();
+        let declared: Vec<String> = computer
+            .register_names
+            .iter()
+            .map(|r| r.0.to_string())
+            .collect();
+
+        writeln!(f, "fn main() {{")?;
+        writeln!(
+            f,
+            "    let mut registers: [i128; {}] = [{}];",
+            registers.len(),
+            registers.join(", ")
+        )?;
+        writeln!(
+            f,
+            "    for (&r, arg) in [{}].iter().zip(std::env::args().skip(1)) {{",
+            declared.join(", ")
+        )?;
+        writeln!(
+            f,
+            "        registers[r] = arg.parse().expect(\"invalid register value\");"
+        )?;
+        writeln!(f, "    }}")?;
+        if self.toggles() {
+            writeln!(f, "    let mut variants = [0; {}];", self.variants.len())?;
+        }
+        writeln!(
+            f,
+            "    let mut pc: i128 = {};",
+            computer.instruction_pointer
+        )?;
+        writeln!(f)?;
+
+        writeln!(f, "    while pc < LEN {{")?;
+        if self.toggles() {
+            writeln!(f, "        match (pc, variants[pc as usize]) {{")?;
+        } else {
+            writeln!(f, "        match pc {{")?;
+        }
+        for (address, variants) in self.variants.iter().enumerate() {
+            for (k, &i) in variants.iter().enumerate() {
+                if self.toggles() {
+                    writeln!(f, "            ({}, {}) => {{", address, k)?;
+                } else {
+                    writeln!(f, "            {} => {{", address)?;
+                }
+                writeln!(f, "                // {}", i)?;
+                for statement in self.statements(i) {
+                    writeln!(f, "                {}", statement)?;
+                }
+                writeln!(f, "            }}")?;
+            }
+        }
+        writeln!(f, "            _ => unreachable!(),")?;
+        writeln!(f, "        }}")?;
+        writeln!(f, "    }}")?;
+        writeln!(f)?;
+
+        for r in &computer.register_names {
+            writeln!(f, "    println!(\"{} = {{}}\", registers[{}]);", r, r.0)?;
+        }
+        writeln!(f, "}}")
+    }
+
+    fn write_c(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let computer = self.computer;
+        let width = computer.width;
+        let variants = self.variants.iter().map(Vec::len).max().unwrap_or(1);
+
+        writeln!(f, "/* Generated from an assembunny program. */")?;
+        writeln!(f)?;
+        writeln!(f, "#include <stdint.h>")?;
+        writeln!(f, "#include <stdio.h>")?;
+        writeln!(f, "#include <stdlib.h>")?;
+        writeln!(f)?;
+        writeln!(f, "typedef __int128 value_t;")?;
+        writeln!(f)?;
+        writeln!(f, "#define MIN {}", self.literal(width.min()))?;
+        writeln!(f, "#define MAX {}", self.literal(width.max()))?;
+        writeln!(f, "#define LEN {}", computer.program.len())?;
+        if self.toggles() {
+            writeln!(
+                f,
+                "static const int NEXT[{}][{}] = {{",
+                self.next.len(),
+                variants
+            )?;
+            for n in &self.next {
+                let row: Vec<String> = (0..variants)
+                    .map(|k| n.get(k).copied().unwrap_or(0).to_string())
+                    .collect();
+                writeln!(f, "    {{{}}},", row.join(", "))?;
+            }
+            writeln!(f, "}};")?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "static void print_value(FILE *stream, value_t v) {{")?;
+        writeln!(
+            f,
+            "    unsigned __int128 u = v < 0 ? -(unsigned __int128)v : (unsigned __int128)v;"
+        )?;
+        writeln!(f, "    char buffer[41];")?;
+        writeln!(f, "    int i = sizeof(buffer) - 1;")?;
+        writeln!(f, "    buffer[i] = '\\0';")?;
+        writeln!(f, "    do {{")?;
+        writeln!(f, "        buffer[--i] = '0' + (int)(u % 10);")?;
+        writeln!(f, "        u /= 10;")?;
+        writeln!(f, "    }} while (u != 0);")?;
+        writeln!(f, "    if (v < 0) {{")?;
+        writeln!(f, "        buffer[--i] = '-';")?;
+        writeln!(f, "    }}")?;
+        writeln!(f, "    fputs(buffer + i, stream);")?;
+        writeln!(f, "}}")?;
+        writeln!(f)?;
+
+        writeln!(f, "static value_t saturating_add(value_t a, value_t b) {{")?;
+        writeln!(f, "    value_t v;")?;
+        writeln!(f, "    if (__builtin_add_overflow(a, b, &v)) {{")?;
+        writeln!(
+            f,
+            "        value_t min = (value_t)((unsigned __int128)1 << 127);"
+        )?;
+        writeln!(f, "        return b < 0 ? min : ~min;")?;
+        writeln!(f, "    }}")?;
+        writeln!(f, "    return v;")?;
+        writeln!(f, "}}")?;
+        writeln!(f)?;
+
+        writeln!(f, "static value_t add(value_t pc, value_t a, value_t b) {{")?;
+        writeln!(f, "    value_t v;")?;
+        writeln!(f, "    int overflow = __builtin_add_overflow(a, b, &v);")?;
+        writeln!(f, "    if (!overflow && MIN <= v && v <= MAX) {{")?;
+        writeln!(f, "        return v;")?;
+        writeln!(f, "    }}")?;
+        match (computer.overflow_policy, width) {
+            (OverflowPolicy::Wrap, Width::I32) => writeln!(f, "    return (int32_t)(uint32_t)v;")?,
+            (OverflowPolicy::Wrap, Width::I64) => writeln!(f, "    return (int64_t)(uint64_t)v;")?,
+            (OverflowPolicy::Wrap, Width::I128) => writeln!(f, "    return v;")?,
+            (OverflowPolicy::Saturate, _) => {
+                writeln!(f, "    if (overflow) {{")?;
+                writeln!(f, "        return b > 0 ? MAX : MIN;")?;
+                writeln!(f, "    }}")?;
+                writeln!(f, "    return v < MIN ? MIN : MAX;")?;
+            }
+            (OverflowPolicy::Error, _) => {
+                writeln!(
+                    f,
+                    "    fprintf(stderr, \"error: overflow at instruction \");"
+                )?;
+                writeln!(f, "    print_value(stderr, pc);")?;
+                writeln!(f, "    fprintf(stderr, \"\\n\");")?;
+                writeln!(f, "    exit(1);")?;
+            }
+        }
+        writeln!(f, "}}")?;
+        writeln!(f)?;
+
+        writeln!(f, "static value_t jump(value_t pc, value_t offset) {{")?;
+        writeln!(f, "    value_t target = saturating_add(pc, offset);")?;
+        writeln!(f, "    if (target < 0 || target > INT32_MAX) {{")?;
+        writeln!(
+            f,
+            "        fprintf(stderr, \"error: invalid instruction pointer \");"
+        )?;
+        writeln!(f, "        print_value(stderr, target);")?;
+        writeln!(f, "        fprintf(stderr, \"\\n\");")?;
+        writeln!(f, "        exit(1);")?;
+        writeln!(f, "    }}")?;
+        writeln!(f, "    return target;")?;
+        writeln!(f, "}}")?;
+        writeln!(f)?;
+
+        if self.toggles() {
+            writeln!(
+                f,
+                "static void toggle(int *variants, value_t pc, value_t offset) {{"
+            )?;
+            writeln!(f, "    value_t target = saturating_add(pc, offset);")?;
+            writeln!(f, "    if (0 <= target && target < LEN) {{")?;
+            writeln!(
+                f,
+                "        variants[(int)target] = NEXT[(int)target][variants[(int)target]];"
+            )?;
+            writeln!(f, "    }}")?;
+            writeln!(f, "}}")?;
+            writeln!(f)?;
+        }
+
+        if self.inputs() {
+            writeln!(f, "static value_t input(value_t pc) {{")?;
+            writeln!(f, "    long long v;")?;
+            writeln!(f, "    if (scanf(\"%lld\", &v) != 1) {{")?;
+            writeln!(
+                f,
+                "        fprintf(stderr, \"error: no input left for instruction \");"
+            )?;
+            writeln!(f, "        print_value(stderr, pc);")?;
+            writeln!(f, "        fprintf(stderr, \"\\n\");")?;
+            writeln!(f, "        exit(1);")?;
+            writeln!(f, "    }}")?;
+            writeln!(f, "    return v;")?;
+            writeln!(f, "}}")?;
+            writeln!(f)?;
+        }
+
+        let registers: Vec<String> = computer
+            .registers
+            .iter()
+            .map(|&Value(v)| self.literal(v))
+            .collect();
+        let declared: Vec<String> = computer
+            .register_names
+            .iter()
+            .map(|r| r.0.to_string())
+            .collect();
+
+        writeln!(f, "int main(int argc, char **argv) {{")?;
+        writeln!(
+            f,
+            "    value_t registers[{}] = {{{}}};",
+            registers.len(),
+            registers.join(", ")
+        )?;
+        writeln!(
+            f,
+            "    static const int declared[] = {{{}}};",
+            declared.join(", ")
+        )?;
+        writeln!(
+            f,
+            "    for (int i = 1; i < argc && i <= {}; i++) {{",
+            declared.len()
+        )?;
+        writeln!(
+            f,
+            "        registers[declared[i - 1]] = strtoll(argv[i], NULL, 10);"
+        )?;
+        writeln!(f, "    }}")?;
+        if self.toggles() {
+            writeln!(f, "    int variants[LEN] = {{0}};")?;
+        }
+        writeln!(f, "    value_t pc = {};", computer.instruction_pointer)?;
+        writeln!(f)?;
+
+        writeln!(f, "    while (pc < LEN) {{")?;
+        writeln!(f, "        switch ((int)pc) {{")?;
+        for (address, variants) in self.variants.iter().enumerate() {
+            writeln!(f, "        case {}:", address)?;
+            if self.toggles() {
+                writeln!(f, "            switch (variants[{}]) {{", address)?;
+                for (k, &i) in variants.iter().enumerate() {
+                    writeln!(f, "            case {}: /* {} */", k, i)?;
+                    for statement in self.statements(i) {
+                        writeln!(f, "                {}", statement)?;
+                    }
+                    writeln!(f, "                break;")?;
+                }
+                writeln!(f, "            }}")?;
+            } else {
+                writeln!(f, "            /* {} */", variants[0])?;
+                for statement in self.statements(variants[0]) {
+                    writeln!(f, "            {}", statement)?;
+                }
+            }
+            writeln!(f, "            break;")?;
+        }
+        writeln!(f, "        }}")?;
+        writeln!(f, "    }}")?;
+        writeln!(f)?;
+
+        for r in &computer.register_names {
+            writeln!(f, "    printf(\"{} = \");", r)?;
+            writeln!(f, "    print_value(stdout, registers[{}]);", r.0)?;
+            writeln!(f, "    printf(\"\\n\");")?;
+        }
+        writeln!(f, "    return 0;")?;
+        writeln!(f, "}}")
+    }
+}
+
+impl<'a> std::fmt::Display for Source<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self.language {
+            Language::Rust => self.write_rust(f),
+            Language::C => self.write_c(f),
+        }
+    }
+}
+
+impl Computer {
+    /// Translate the program, as it currently stands, to standalone source in `language`.
+    pub fn transpile(&self, language: Language) -> Source<'_> {
+        Source::new(self, language)
+    }
+}
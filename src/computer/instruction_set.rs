@@ -0,0 +1,347 @@
+//! Register machines with a pluggable instruction set.
+//!
+//! An `InstructionSet` maps opcodes to instructions, and executes them against a `Context`: the
+//! registers, instruction pointer and I/O of whatever machine runs them. A `Machine` runs any set,
+//! with the same syntax, registers, overflow handling, ports and debugging tools. Assembunny's
+//! `Instruction` is one such set, whose `Machine` is the `Computer`. Other sets, such as
+//! `ExtendedInstruction` with its `mul`, `nop`, `snd` and `rcv`, run on a `Machine` of their own,
+//! without the assembunny-specific tooling (optimizations, snapshots, analysis, ...).
+
+use super::{
+    assembler, Argument, ComputerError, ComputerResult, Instruction, Machine, Register, Value,
+};
+use std::convert::TryFrom;
+
+/// The state of a machine, as seen by the instruction it is executing.
+pub trait Context {
+    /// The value of an argument: itself if it is a value, or what the register holds.
+    fn get(&self, argument: Argument) -> i128;
+
+    /// Set a register, as is, even if the value does not fit in the machine's `Width`.
+    fn set(&mut self, register: Register, value: i128);
+
+    /// Add two values, applying the machine's overflow policy.
+    fn add(&self, a: i128, b: i128) -> ComputerResult<i128>;
+
+    /// Multiply two values, applying the machine's overflow policy.
+    fn multiply(&self, a: i128, b: i128) -> ComputerResult<i128>;
+
+    /// Address of the instruction being executed.
+    fn instruction_pointer(&self) -> i32;
+
+    /// Move the instruction pointer by `offset`. Every instruction must do so, by 1 to move on to
+    /// the next one, unless it has to wait for input.
+    fn jump(&mut self, offset: i128) -> ComputerResult<()>;
+
+    /// Replace the instruction at this address with `InstructionSet::toggle` of itself. Nothing
+    /// happens if the address is outside the program.
+    fn toggle(&mut self, address: i128);
+
+    fn send(&mut self, value: i128);
+
+    /// The next input value, if any. Otherwise, the instruction should leave the instruction
+    /// pointer as is, so it runs again once there is some input.
+    fn receive(&mut self) -> Option<i128>;
+}
+
+/// Builds an instruction from its arguments, which are as many as its opcode takes.
+pub type Constructor<I> = fn(&[Argument]) -> I;
+
+/// A set of instructions, written as an opcode followed by its arguments, each a value or a
+/// register.
+pub trait InstructionSet: Copy + Eq + std::fmt::Debug + std::fmt::Display {
+    /// Number of arguments of an opcode and how to build the instruction from them, or `None` if
+    /// the opcode is not part of the set.
+    fn decode(opcode: &str) -> Option<(usize, Constructor<Self>)>;
+
+    fn execute<C: Context + ?Sized>(self, machine: &mut C) -> ComputerResult<()>;
+
+    /// What `tgl` turns this instruction into. Sets without `tgl` can keep the default.
+    fn toggle(self) -> Self {
+        self
+    }
+
+    /// Whether the instruction takes a value from `Context::receive`. The machine only executes
+    /// it once one is available, and reports it as blocked until then.
+    fn reads_input(self) -> bool {
+        false
+    }
+}
+
+impl InstructionSet for Instruction {
+    fn decode(opcode: &str) -> Option<(usize, Constructor<Self>)> {
+        match opcode {
+            "cpy" => Some((2, |args| Instruction::Copy(args[0], args[1]))),
+            "inc" => Some((1, |args| Instruction::Increment(args[0]))),
+            "dec" => Some((1, |args| Instruction::Decrement(args[0]))),
+            "jnz" => Some((2, |args| Instruction::JumpIfNotZero(args[0], args[1]))),
+            "tgl" => Some((1, |args| Instruction::Toggle(args[0]))),
+            "out" => Some((1, |args| Instruction::Out(args[0]))),
+            "in" => Some((1, |args| Instruction::In(args[0]))),
+            _ => None,
+        }
+    }
+
+    fn execute<C: Context + ?Sized>(self, machine: &mut C) -> ComputerResult<()> {
+        match self {
+            Instruction::Copy(a, b) => {
+                let a = match a {
+                    Argument::Register(_) => machine.get(a),
+                    Argument::Value(Value(v)) => machine.add(v, 0)?,
+                };
+
+                if let Argument::Register(r) = b {
+                    machine.set(r, a);
+                }
+            }
+            Instruction::Increment(Argument::Register(r)) => {
+                let a = machine.add(machine.get(Argument::Register(r)), 1)?;
+                machine.set(r, a);
+            }
+            Instruction::Decrement(Argument::Register(r)) => {
+                let a = machine.add(machine.get(Argument::Register(r)), -1)?;
+                machine.set(r, a);
+            }
+            Instruction::Increment(_) | Instruction::Decrement(_) => {}
+            Instruction::JumpIfNotZero(a, b) => {
+                if machine.get(a) != 0 {
+                    return machine.jump(machine.get(b));
+                }
+            }
+            Instruction::Toggle(a) => {
+                let target = machine
+                    .get(a)
+                    .saturating_add(machine.instruction_pointer().into());
+                machine.toggle(target);
+            }
+            Instruction::Out(a) => machine.send(machine.get(a)),
+            Instruction::In(a) => match (machine.receive(), a) {
+                (Some(v), Argument::Register(r)) => machine.set(r, v),
+                (Some(_), Argument::Value(_)) => (),
+                (None, _) => return Ok(()),
+            },
+        }
+
+        machine.jump(1)
+    }
+
+    fn toggle(self) -> Self {
+        match self {
+            Instruction::Copy(a, b) => Instruction::JumpIfNotZero(a, b),
+            Instruction::Increment(a) => Instruction::Decrement(a),
+            Instruction::Decrement(a) => Instruction::Increment(a),
+            Instruction::JumpIfNotZero(a, b) => Instruction::Copy(a, b),
+            Instruction::Toggle(a) => Instruction::Increment(a),
+            Instruction::Out(a) | Instruction::In(a) => Instruction::Increment(a),
+        }
+    }
+    fn reads_input(self) -> bool {
+        matches!(self, Instruction::In(_))
+    }
+}
+
+/// Assembunny without `tgl`, `out` and `in`, but with the `mul`, `nop`, `snd` and `rcv` of later
+/// register machines. It runs on a `Machine`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExtendedInstruction {
+    Copy(Argument, Argument),
+    Increment(Argument),
+    Decrement(Argument),
+    JumpIfNotZero(Argument, Argument),
+    /// Multiply the register by the value.
+    Multiply(Argument, Argument),
+    Nop,
+    Send(Argument),
+    /// Wait for a value, and store it in the register.
+    Receive(Argument),
+}
+
+impl std::fmt::Display for ExtendedInstruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ExtendedInstruction::Copy(a, b) => write!(f, "cpy {} {}", a, b),
+            ExtendedInstruction::Increment(a) => write!(f, "inc {}", a),
+            ExtendedInstruction::Decrement(a) => write!(f, "dec {}", a),
+            ExtendedInstruction::JumpIfNotZero(a, b) => write!(f, "jnz {} {}", a, b),
+            ExtendedInstruction::Multiply(a, b) => write!(f, "mul {} {}", a, b),
+            ExtendedInstruction::Nop => write!(f, "nop"),
+            ExtendedInstruction::Send(a) => write!(f, "snd {}", a),
+            ExtendedInstruction::Receive(a) => write!(f, "rcv {}", a),
+        }
+    }
+}
+
+impl InstructionSet for ExtendedInstruction {
+    fn decode(opcode: &str) -> Option<(usize, Constructor<Self>)> {
+        match opcode {
+            "cpy" => Some((2, |args| ExtendedInstruction::Copy(args[0], args[1]))),
+            "inc" => Some((1, |args| ExtendedInstruction::Increment(args[0]))),
+            "dec" => Some((1, |args| ExtendedInstruction::Decrement(args[0]))),
+            "jnz" => Some((2, |args| {
+                ExtendedInstruction::JumpIfNotZero(args[0], args[1])
+            })),
+            "mul" => Some((2, |args| ExtendedInstruction::Multiply(args[0], args[1]))),
+            "nop" => Some((0, |_| ExtendedInstruction::Nop)),
+            "snd" => Some((1, |args| ExtendedInstruction::Send(args[0]))),
+            "rcv" => Some((1, |args| ExtendedInstruction::Receive(args[0]))),
+            _ => None,
+        }
+    }
+
+    /// The instructions shared with assembunny behave exactly as they do there.
+    fn execute<C: Context + ?Sized>(self, machine: &mut C) -> ComputerResult<()> {
+        match self {
+            ExtendedInstruction::Copy(a, b) => Instruction::Copy(a, b).execute(machine),
+            ExtendedInstruction::Increment(a) => Instruction::Increment(a).execute(machine),
+            ExtendedInstruction::Decrement(a) => Instruction::Decrement(a).execute(machine),
+            ExtendedInstruction::JumpIfNotZero(a, b) => {
+                Instruction::JumpIfNotZero(a, b).execute(machine)
+            }
+            ExtendedInstruction::Multiply(Argument::Register(r), b) => {
+                let a = machine.multiply(machine.get(Argument::Register(r)), machine.get(b))?;
+                machine.set(r, a);
+                machine.jump(1)
+            }
+            ExtendedInstruction::Multiply(Argument::Value(_), _) | ExtendedInstruction::Nop => {
+                machine.jump(1)
+            }
+            ExtendedInstruction::Send(a) => Instruction::Out(a).execute(machine),
+            ExtendedInstruction::Receive(a) => Instruction::In(a).execute(machine),
+        }
+    }
+    fn reads_input(self) -> bool {
+        matches!(self, ExtendedInstruction::Receive(_))
+    }
+}
+
+/// Parse a single line, returning every error found along with its byte offset in `line`.
+/// Anything following a `;` is a comment, and only the given `registers` may be used. Other names
+/// are looked up with `symbols`.
+pub(super) fn parse<I: InstructionSet>(
+    line: &str,
+    registers: &[Register],
+    symbols: &dyn Fn(&str) -> Option<i128>,
+) -> Result<I, Vec<(usize, ComputerError)>> {
+    let offset = |token: &str| token.as_ptr() as usize - line.as_ptr() as usize;
+    let code = line.split(';').next().unwrap_or_default();
+    let mut tokens = code.split_whitespace();
+
+    let opcode = tokens.next().unwrap_or_default();
+    let (arity, build) = match I::decode(opcode) {
+        Some(decoded) => decoded,
+        None => {
+            let column = if opcode.is_empty() { 0 } else { offset(opcode) };
+            let error = ComputerError::InvalidOpCode(opcode.to_string());
+            return Err(vec![(column, error)]);
+        }
+    };
+
+    let mut arguments = Vec::with_capacity(arity);
+    let mut errors = Vec::new();
+    for _ in 0..arity {
+        match tokens.next() {
+            Some(token) => match Argument::try_from(token) {
+                Ok(Argument::Register(r)) if !registers.contains(&r) => {
+                    let error = ComputerError::InvalidRegister(token.to_string());
+                    errors.push((offset(token), error));
+                }
+                Ok(argument) => arguments.push(argument),
+                Err(error) => match symbols(token) {
+                    Some(v) => arguments.push(Argument::Value(Value(v))),
+                    None if assembler::is_symbol(token) => {
+                        let error = ComputerError::UndefinedSymbol(token.to_string());
+                        errors.push((offset(token), error));
+                    }
+                    None => errors.push((offset(token), error)),
+                },
+            },
+            None => {
+                let error = ComputerError::MissingArgument(opcode.to_string());
+                errors.push((code.trim_end().len(), error));
+                break;
+            }
+        }
+    }
+    for token in tokens {
+        let error = ComputerError::UnexpectedArgument(token.to_string());
+        errors.push((offset(token), error));
+    }
+
+    if errors.is_empty() {
+        Ok(build(&arguments))
+    } else {
+        Err(errors)
+    }
+}
+
+impl<I: InstructionSet> Context for Machine<I> {
+    fn get(&self, argument: Argument) -> i128 {
+        match argument {
+            Argument::Value(Value(v)) => v,
+            Argument::Register(Register(r)) => self.registers[r].0,
+        }
+    }
+
+    fn set(&mut self, Register(r): Register, value: i128) {
+        self.registers[r] = Value(value);
+    }
+
+    fn add(&self, a: i128, b: i128) -> ComputerResult<i128> {
+        self.overflow_policy.fit(
+            self.width,
+            self.instruction_pointer,
+            a.checked_add(b),
+            a.wrapping_add(b),
+            a.saturating_add(b),
+        )
+    }
+
+    fn multiply(&self, a: i128, b: i128) -> ComputerResult<i128> {
+        self.overflow_policy.fit(
+            self.width,
+            self.instruction_pointer,
+            a.checked_mul(b),
+            a.wrapping_mul(b),
+            a.saturating_mul(b),
+        )
+    }
+
+    fn instruction_pointer(&self) -> i32 {
+        self.instruction_pointer
+    }
+
+    fn jump(&mut self, offset: i128) -> ComputerResult<()> {
+        self.instruction_pointer = jump_target(self.instruction_pointer, offset)?;
+        Ok(())
+    }
+
+    /// Toggling also re-runs the optimizer, and lets the loop detector know about the new program.
+    fn toggle(&mut self, address: i128) {
+        if let Ok(address) = usize::try_from(address) {
+            if let Some(i) = self.program.get_mut(address) {
+                *i = i.toggle();
+                self.recognize_loops();
+                if let Some(loop_detector) = &mut self.loop_detector {
+                    loop_detector.program_changed(&self.program);
+                }
+            }
+        }
+    }
+
+    fn send(&mut self, value: i128) {
+        self.output.push(value);
+        self.ports.send(value);
+    }
+
+    fn receive(&mut self) -> Option<i128> {
+        self.receive_input()
+    }
+}
+
+fn jump_target(instruction_pointer: i32, offset: i128) -> ComputerResult<i32> {
+    let target = offset.saturating_add(instruction_pointer.into());
+    match i32::try_from(target) {
+        Ok(ip) if ip >= 0 => Ok(ip),
+        _ => Err(ComputerError::InvalidInstructionPointer(target)),
+    }
+}
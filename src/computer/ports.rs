@@ -0,0 +1,169 @@
+//! Input and output ports, through which `in` and `out` instructions exchange values with the host
+//! or with other computers, possibly running on other threads:
+//!
+//! ```text
+//! let (sender, receiver) = std::sync::mpsc::channel();
+//! producer.connect_output(sender);
+//! consumer.connect_input(receiver);
+//! ```
+
+use super::instruction_set::InstructionSet;
+use super::{ComputerError, ComputerResult, Machine, Status, Step};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+enum Input {
+    Channel(Receiver<i128>),
+    Iterator(Box<dyn Iterator<Item = i128> + Send>),
+}
+
+enum Output {
+    Channel(Sender<i128>),
+    Sink(Box<dyn FnMut(i128) + Send>),
+}
+
+/// The ports of a computer, which its clones share.
+#[derive(Clone, Default)]
+pub(super) struct Ports {
+    input: Option<Arc<Mutex<Input>>>,
+    output: Option<Arc<Mutex<Output>>>,
+}
+
+impl std::fmt::Debug for Ports {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.debug_struct("Ports")
+            .field("input", &self.input.is_some())
+            .field("output", &self.output.is_some())
+            .finish()
+    }
+}
+
+/// Lock a port, even if a sink panicked while holding it.
+fn lock<T>(port: &Mutex<T>) -> MutexGuard<'_, T> {
+    port.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl Ports {
+    /// The next value from the input port. Unless `wait` is set, a channel only gives a value that
+    /// is already there.
+    fn receive(&self, wait: bool) -> Option<i128> {
+        match &mut *lock(self.input.as_ref()?) {
+            Input::Channel(receiver) if wait => receiver.recv().ok(),
+            Input::Channel(receiver) => receiver.try_recv().ok(),
+            Input::Iterator(iterator) => iterator.next(),
+        }
+    }
+
+    /// Pass a value to the output port. Values sent to a channel whose receiver is gone are lost.
+    pub(super) fn send(&self, value: i128) {
+        if let Some(output) = &self.output {
+            match &mut *lock(output) {
+                Output::Channel(sender) => {
+                    let _ = sender.send(value);
+                }
+                Output::Sink(sink) => sink(value),
+            }
+        }
+    }
+}
+
+impl<I: InstructionSet> Machine<I> {
+    /// Take the values for `in` instructions from a channel, once those queued with `input` are
+    /// used up.
+    pub fn connect_input(&mut self, receiver: Receiver<i128>) -> &mut Self {
+        self.ports.input = Some(Arc::new(Mutex::new(Input::Channel(receiver))));
+        self
+    }
+
+    /// Take the values for `in` instructions from an iterator, once those queued with `input` are
+    /// used up.
+    pub fn set_input<T>(&mut self, input: T) -> &mut Self
+    where
+        T: IntoIterator<Item = i128>,
+        T::IntoIter: Send + 'static,
+    {
+        let iterator = Box::new(input.into_iter());
+        self.ports.input = Some(Arc::new(Mutex::new(Input::Iterator(iterator))));
+        self
+    }
+
+    /// Send every value transmitted by `out` to a channel, besides keeping it in `output`.
+    pub fn connect_output(&mut self, sender: Sender<i128>) -> &mut Self {
+        self.ports.output = Some(Arc::new(Mutex::new(Output::Channel(sender))));
+        self
+    }
+
+    /// Pass every value transmitted by `out` to `sink`, besides keeping it in `output`.
+    pub fn set_output<F: FnMut(i128) + Send + 'static>(&mut self, sink: F) -> &mut Self {
+        self.ports.output = Some(Arc::new(Mutex::new(Output::Sink(Box::new(sink)))));
+        self
+    }
+
+    /// Queue a value for the `in` instructions.
+    pub fn input(&mut self, value: i128) -> &mut Self {
+        self.input.push_back(value);
+        self
+    }
+
+    /// Run the program until it transmits a value, until it needs a value that is not available
+    /// yet, or until it halts. Unlike `run`, this never waits for input, so that the caller can
+    /// provide some before resuming.
+    pub fn resume(&mut self) -> ComputerResult<Status> {
+        loop {
+            let sent = self.output.len();
+            match self.step()? {
+                Step::Halted => return Ok(Status::Halted),
+                Step::Blocked { .. } => return Ok(Status::Blocked),
+                _ if self.output.len() > sent => return Ok(Status::Output(self.output[sent])),
+                _ => (),
+            }
+        }
+    }
+
+    /// Whether a value is available for `in`, moving it from the input port to the queue if need
+    /// be.
+    pub(super) fn poll_input(&mut self) -> bool {
+        if self.input.is_empty() {
+            if let Some(value) = self.ports.receive(false) {
+                self.input.push_back(value);
+            }
+        }
+
+        !self.input.is_empty()
+    }
+
+    /// Wait for a value for the `in` instruction at the instruction pointer.
+    pub(super) fn wait_for_input(&mut self) -> ComputerResult<()> {
+        match self.ports.receive(true) {
+            Some(value) => {
+                self.input.push_back(value);
+                Ok(())
+            }
+            None => Err(ComputerError::NoInput(self.instruction_pointer)),
+        }
+    }
+
+    /// Like `step`, but when blocked, wait for input and try again.
+    pub(super) fn step_or_wait(&mut self) -> ComputerResult<Step<I>> {
+        match self.step()? {
+            Step::Blocked { .. } => {
+                self.wait_for_input()?;
+                self.step()
+            }
+            step => Ok(step),
+        }
+    }
+
+    /// Take the next value for `in`, if there is one. Since it may not be the same each time, the
+    /// states seen so far no longer prove that the program is stuck in a loop.
+    pub(super) fn receive_input(&mut self) -> Option<i128> {
+        if !self.poll_input() {
+            return None;
+        }
+        if let Some(loop_detector) = &mut self.loop_detector {
+            loop_detector.states.clear();
+        }
+
+        self.input.pop_front()
+    }
+}
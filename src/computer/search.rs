@@ -0,0 +1,164 @@
+//! Brute-force search for the initial value of a register that makes a program behave a certain
+//! way, trying candidates in parallel on a copy of the computer each.
+
+use super::ports::Ports;
+use super::{Computer, ComputerError, ComputerResult, Register, Step};
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Condition on which a candidate stops early.
+type Stop<'a> = Box<dyn Fn(&Computer) -> bool + Sync + 'a>;
+
+/// A search over the initial values of a register, as returned by `Computer::search`.
+///
+/// Each candidate runs on a clone of the computer, with the same settings (optimizations, loop
+/// detection, ...), until it halts, fails, exceeds its step limit or meets the stop condition. The
+/// predicate then decides from the final state and the result of the run whether it matches.
+///
+/// The clones are detached from the computer's ports, which would otherwise be shared between
+/// candidates: their output is only recorded, and their `in` instructions only take the values
+/// already queued with `input`, failing with `ComputerError::NoInput` once there are none left.
+pub struct Search<'a> {
+    computer: &'a Computer,
+    register: Register,
+    values: Range<i128>,
+    max_steps: Option<usize>,
+    threads: usize,
+    stop: Option<Stop<'a>>,
+}
+
+impl std::fmt::Debug for Search<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        f.debug_struct("Search")
+            .field("register", &self.register)
+            .field("values", &self.values)
+            .field("max_steps", &self.max_steps)
+            .field("threads", &self.threads)
+            .finish()
+    }
+}
+
+impl<'a> Search<'a> {
+    pub(super) fn new(
+        computer: &'a Computer,
+        register: Register,
+        values: Range<i128>,
+    ) -> ComputerResult<Self> {
+        if !computer.register_names.contains(&register) {
+            return Err(ComputerError::InvalidRegister(register.to_string()));
+        }
+
+        Ok(Search {
+            computer,
+            register,
+            values,
+            max_steps: None,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            stop: None,
+        })
+    }
+
+    /// Stop each candidate with `ComputerError::StepLimitExceeded` after this many steps (an
+    /// optimized loop counting as a single step).
+    pub fn max_steps(&mut self, max_steps: usize) -> &mut Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Number of candidates run at the same time. Defaults to the available parallelism.
+    pub fn threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Stop each candidate as soon as `stop` holds (it is checked before every step), e.g. once
+    /// its output goes wrong.
+    pub fn stop_when<F: Fn(&Computer) -> bool + Sync + 'a>(&mut self, stop: F) -> &mut Self {
+        self.stop = Some(Box::new(stop));
+        self
+    }
+
+    /// Any value for which `predicate` holds, whichever is found first, along with the final
+    /// state of its computer.
+    pub fn first<P>(&self, predicate: P) -> Option<(i128, Computer)>
+    where
+        P: Fn(&Computer, &Result<(), ComputerError>) -> bool + Sync,
+    {
+        self.find(predicate, false)
+    }
+
+    /// The lowest value for which `predicate` holds, along with the final state of its computer.
+    pub fn minimal<P>(&self, predicate: P) -> Option<(i128, Computer)>
+    where
+        P: Fn(&Computer, &Result<(), ComputerError>) -> bool + Sync,
+    {
+        self.find(predicate, true)
+    }
+
+    fn find<P>(&self, predicate: P, minimal: bool) -> Option<(i128, Computer)>
+    where
+        P: Fn(&Computer, &Result<(), ComputerError>) -> bool + Sync,
+    {
+        let len = if self.values.is_empty() {
+            0
+        } else {
+            usize::try_from(self.values.end.wrapping_sub(self.values.start) as u128)
+                .unwrap_or(usize::MAX)
+        };
+        // Candidates are handed out in order; `best` is the index of the best match so far.
+        let next = AtomicUsize::new(0);
+        let best = AtomicUsize::new(usize::MAX);
+        let found = Mutex::new(None);
+
+        let worker = || loop {
+            let idx = next.fetch_add(1, Ordering::Relaxed);
+            let best_idx = best.load(Ordering::Relaxed);
+            if idx >= len || (minimal && idx > best_idx) || (!minimal && best_idx != usize::MAX) {
+                return;
+            }
+
+            let value = self.values.start + idx as i128;
+            let (computer, result) = self.run(value);
+            if predicate(&computer, &result) {
+                let mut found = found.lock().unwrap();
+                if best.fetch_min(idx, Ordering::Relaxed) > idx {
+                    *found = Some((value, computer));
+                }
+            }
+        };
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.threads {
+                scope.spawn(worker);
+            }
+        });
+
+        found.into_inner().unwrap()
+    }
+
+    fn run(&self, value: i128) -> (Computer, Result<(), ComputerError>) {
+        let mut computer = self.computer.clone();
+        computer.ports = Ports::default();
+        computer.registers[self.register.0].0 = value;
+
+        let mut steps = 0;
+        let result = loop {
+            if self.stop.as_ref().is_some_and(|stop| stop(&computer)) {
+                break Ok(());
+            }
+            if self.max_steps == Some(steps) {
+                break Err(ComputerError::StepLimitExceeded(steps));
+            }
+
+            match computer.step_or_wait() {
+                Ok(Step::Halted) => break Ok(()),
+                Ok(_) => steps += 1,
+                Err(e) => break Err(e),
+            }
+        };
+
+        (computer, result)
+    }
+}
@@ -0,0 +1,1041 @@
+//! Symbolic evaluation of assembunny programs: registers hold polynomials of the initial values of
+//! some registers, instead of numbers.
+//!
+//! Straight-line code is simply executed on polynomials. When the program jumps back to the start
+//! of a loop, the body of the loop is evaluated once more from fresh variables, to find a counter
+//! (a register moving by one on every iteration) and the conditions under which the loop keeps
+//! taking the same path. If every other register is incremented by an amount that does not change
+//! from one iteration to the next, reset to such a value, or multiplied by the counter, the
+//! remaining iterations are applied at once; loops nested in the body are summarized the same way,
+//! and the product of the counters is written with a factorial.
+//!
+//! Conditions that cannot be decided are assumed to go the way they do for large values of the
+//! initial registers, and are listed in the `Summary`. Loops are assumed to terminate, and to run
+//! more than once whenever they are reached. Values are unbounded, whatever the computer's `Width`.
+
+use super::instruction_set::InstructionSet;
+use super::{Argument, Computer, ComputerError, ComputerResult, Instruction, Register, Value};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+/// Number of instructions evaluated, loop bodies included, before giving up.
+const MAX_STEPS: usize = 1_000_000;
+/// Variables below this one stand for the initial value of the register with the same index.
+const FIRST_FRESH_VARIABLE: usize = 26;
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Arithmetic on rationals, and so on polynomials, returns `None` on overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Rational {
+    numerator: i128,
+    /// Always positive, and coprime with the numerator.
+    denominator: i128,
+}
+
+impl Rational {
+    fn new(numerator: i128, denominator: i128) -> Option<Self> {
+        let g = i128::try_from(gcd(numerator.unsigned_abs(), denominator.unsigned_abs())).ok()?;
+        let g = g.max(1).checked_mul(denominator.signum())?;
+        Some(Rational {
+            numerator: numerator.checked_div(g)?,
+            denominator: denominator.checked_div(g)?,
+        })
+    }
+
+    fn integer(value: i128) -> Self {
+        Rational {
+            numerator: value,
+            denominator: 1,
+        }
+    }
+
+    fn is_zero(self) -> bool {
+        self.numerator == 0
+    }
+
+    fn to_integer(self) -> Option<i128> {
+        if self.denominator == 1 {
+            Some(self.numerator)
+        } else {
+            None
+        }
+    }
+
+    fn add(self, other: Self) -> Option<Self> {
+        let a = self.numerator.checked_mul(other.denominator)?;
+        let b = other.numerator.checked_mul(self.denominator)?;
+        Rational::new(
+            a.checked_add(b)?,
+            self.denominator.checked_mul(other.denominator)?,
+        )
+    }
+
+    fn mul(self, other: Self) -> Option<Self> {
+        let a = Rational::new(self.numerator, other.denominator)?;
+        let b = Rational::new(other.numerator, self.denominator)?;
+        Rational::new(
+            a.numerator.checked_mul(b.numerator)?,
+            a.denominator.checked_mul(b.denominator)?,
+        )
+    }
+
+    fn neg(self) -> Option<Self> {
+        Rational::new(self.numerator.checked_neg()?, self.denominator)
+    }
+
+    /// `1 / self`.
+    fn inverse(self) -> Option<Self> {
+        Rational::new(self.denominator, self.numerator)
+    }
+
+    fn floor(self) -> i128 {
+        self.numerator.div_euclid(self.denominator)
+    }
+
+    fn ceil(self) -> i128 {
+        let floor = self.floor();
+        if self.numerator.rem_euclid(self.denominator) == 0 {
+            floor
+        } else {
+            floor + 1
+        }
+    }
+}
+
+impl std::fmt::Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Factor {
+    Variable(usize),
+    Factorial(Polynomial),
+}
+
+/// Product of factors, with their exponents.
+type Monomial = BTreeMap<Factor, u32>;
+
+/// A polynomial with rational coefficients, whose variables are the initial values of registers
+/// and factorials of polynomials.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Polynomial {
+    terms: BTreeMap<Monomial, Rational>,
+}
+
+impl From<i128> for Polynomial {
+    fn from(value: i128) -> Self {
+        Polynomial::rational(Rational::integer(value))
+    }
+}
+
+impl Polynomial {
+    fn rational(value: Rational) -> Self {
+        Polynomial::monomial(Monomial::new(), value)
+    }
+
+    fn monomial(monomial: Monomial, coefficient: Rational) -> Self {
+        let mut terms = BTreeMap::new();
+        if !coefficient.is_zero() {
+            terms.insert(monomial, coefficient);
+        }
+        Polynomial { terms }
+    }
+
+    fn variable(variable: usize) -> Self {
+        let mut monomial = Monomial::new();
+        monomial.insert(Factor::Variable(variable), 1);
+        Polynomial::monomial(monomial, Rational::integer(1))
+    }
+
+    /// `argument!`, computed if `argument` is a constant.
+    fn factorial(argument: Polynomial) -> Option<Self> {
+        match argument.as_rational() {
+            Some(n) => {
+                let n = n.to_integer().filter(|&n| n >= 0)?;
+                let mut result: i128 = 1;
+                for k in 2..=n {
+                    result = result.checked_mul(k)?;
+                }
+                Some(Polynomial::from(result))
+            }
+            None => {
+                let mut monomial = Monomial::new();
+                monomial.insert(Factor::Factorial(argument), 1);
+                Some(Polynomial::monomial(monomial, Rational::integer(1)))
+            }
+        }
+    }
+
+    fn add(&self, other: &Polynomial) -> Option<Polynomial> {
+        let mut terms = self.terms.clone();
+        for (monomial, &coefficient) in &other.terms {
+            let sum = match terms.get(monomial) {
+                Some(&c) => c.add(coefficient)?,
+                None => coefficient,
+            };
+            if sum.is_zero() {
+                terms.remove(monomial);
+            } else {
+                terms.insert(monomial.clone(), sum);
+            }
+        }
+        Some(Polynomial { terms })
+    }
+
+    fn scale(&self, factor: Rational) -> Option<Polynomial> {
+        if factor.is_zero() {
+            return Some(Polynomial::default());
+        }
+        let terms = self
+            .terms
+            .iter()
+            .map(|(m, &c)| Some((m.clone(), c.mul(factor)?)))
+            .collect::<Option<_>>()?;
+        Some(Polynomial { terms })
+    }
+
+    fn sub(&self, other: &Polynomial) -> Option<Polynomial> {
+        self.add(&other.scale(Rational::integer(-1))?)
+    }
+
+    fn mul(&self, other: &Polynomial) -> Option<Polynomial> {
+        let mut result = Polynomial::default();
+        for (a, &x) in &self.terms {
+            for (b, &y) in &other.terms {
+                let mut monomial = a.clone();
+                for (factor, &exponent) in b {
+                    let power = monomial.entry(factor.clone()).or_insert(0);
+                    *power = power.checked_add(exponent)?;
+                }
+                result = result.add(&Polynomial::monomial(monomial, x.mul(y)?))?;
+            }
+        }
+        Some(result)
+    }
+
+    fn as_rational(&self) -> Option<Rational> {
+        match self.terms.len() {
+            0 => Some(Rational::integer(0)),
+            1 => self.terms.get(&Monomial::new()).copied(),
+            _ => None,
+        }
+    }
+
+    /// The value of the polynomial, if it does not depend on any variable.
+    pub fn as_constant(&self) -> Option<i128> {
+        self.as_rational().and_then(Rational::to_integer)
+    }
+
+    fn mentions(&self, variable: usize) -> bool {
+        self.terms
+            .keys()
+            .flat_map(|m| m.keys())
+            .any(|factor| match factor {
+                Factor::Variable(v) => *v == variable,
+                Factor::Factorial(p) => p.mentions(variable),
+            })
+    }
+
+    /// Replace every variable `v` with `value(v)`, or `None` if a factorial becomes too large or
+    /// negative, or on overflow.
+    fn substitute(&self, value: &dyn Fn(usize) -> Polynomial) -> Option<Polynomial> {
+        let mut result = Polynomial::default();
+        for (monomial, &coefficient) in &self.terms {
+            let mut term = Polynomial::rational(coefficient);
+            for (factor, &exponent) in monomial {
+                let factor = match factor {
+                    Factor::Variable(v) => value(*v),
+                    Factor::Factorial(p) => Polynomial::factorial(p.substitute(value)?)?,
+                };
+                for _ in 0..exponent {
+                    term = term.mul(&factor)?;
+                }
+            }
+            result = result.add(&term)?;
+        }
+        Some(result)
+    }
+
+    /// Evaluate the polynomial, given the initial value of every register. Returns `None` if the
+    /// result is not an integer that fits in an `i128`.
+    pub fn evaluate<F: Fn(Register) -> i128>(&self, initial: F) -> Option<i128> {
+        self.substitute(&|v| Polynomial::from(initial(Register(v))))?
+            .as_constant()
+    }
+
+    /// `(a, b)` such that the polynomial is `a * variable + b`.
+    fn linear(&self, variable: usize) -> Option<(Rational, Rational)> {
+        let mut a = Rational::integer(0);
+        let mut b = Rational::integer(0);
+        for (monomial, &coefficient) in &self.terms {
+            match monomial.iter().next() {
+                None => b = coefficient,
+                Some((Factor::Variable(v), 1)) if *v == variable && monomial.len() == 1 => {
+                    a = coefficient
+                }
+                _ => return None,
+            }
+        }
+        Some((a, b))
+    }
+
+    /// The only variable of the polynomial, outside of factorials.
+    fn single_variable(&self) -> Option<usize> {
+        let mut variables = self.terms.keys().flat_map(|m| m.keys());
+        match variables.next() {
+            Some(&Factor::Variable(v)) if variables.all(|f| *f == Factor::Variable(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// `(q, r)` such that the polynomial is `q * variable + r`, with neither mentioning `variable`.
+    fn split(&self, variable: usize) -> Option<(Polynomial, Polynomial)> {
+        let mut q = Polynomial::default();
+        let mut r = Polynomial::default();
+        for (monomial, &coefficient) in &self.terms {
+            let mut monomial = monomial.clone();
+            match monomial.remove(&Factor::Variable(variable)) {
+                None => r = r.add(&Polynomial::monomial(monomial, coefficient))?,
+                Some(1) => q = q.add(&Polynomial::monomial(monomial, coefficient))?,
+                Some(_) => return None,
+            }
+        }
+        if q.mentions(variable) || r.mentions(variable) {
+            None
+        } else {
+            Some((q, r))
+        }
+    }
+
+    /// Sign of the polynomial when every variable grows to infinity at the same rate, taking
+    /// `(x + c)!` to grow like `x! * x^c`. `None` for factorials of anything else, or on overflow.
+    fn asymptotic_sign(&self) -> Option<Ordering> {
+        let mut growth: BTreeMap<(usize, i128), Rational> = BTreeMap::new();
+        for (monomial, &coefficient) in &self.terms {
+            let (mut factorials, mut degree) = (0, 0);
+            for (factor, &exponent) in monomial {
+                match factor {
+                    Factor::Variable(_) => degree += i128::from(exponent),
+                    Factor::Factorial(p) => {
+                        let (a, b) = p.linear(p.single_variable()?)?;
+                        if a != Rational::integer(1) {
+                            return None;
+                        }
+                        factorials += exponent as usize;
+                        let shift = b.to_integer()?.checked_mul(i128::from(exponent))?;
+                        degree = shift.checked_add(degree)?;
+                    }
+                }
+            }
+            let sum = growth
+                .entry((factorials, degree))
+                .or_insert(Rational::integer(0));
+            *sum = sum.add(coefficient)?;
+        }
+
+        let leading = growth.values().rev().find(|c| !c.is_zero());
+        Some(leading.map_or(Ordering::Equal, |c| c.numerator.cmp(&0)))
+    }
+
+    /// Rewrite `x * (x - 1)!` as `x!`, wherever possible.
+    fn simplify(&self) -> Polynomial {
+        let mut result = self.clone();
+        'simplify: loop {
+            for monomial in result.terms.keys() {
+                for factor in monomial.keys() {
+                    if let Some(simplified) = result.absorb(monomial, factor) {
+                        result = simplified;
+                        continue 'simplify;
+                    }
+                }
+            }
+            return result;
+        }
+    }
+
+    /// If `factor` is `(x + c)!`, and the terms sharing the rest of `monomial` (powers of `x`
+    /// aside) add up to a multiple of `(x + c + 1)`, fold that into the factorial.
+    fn absorb(&self, monomial: &Monomial, factor: &Factor) -> Option<Polynomial> {
+        let argument = match factor {
+            Factor::Factorial(p) if monomial[factor] == 1 => p,
+            _ => return None,
+        };
+        let x = argument.single_variable()?;
+        let (a, c) = argument.linear(x)?;
+        if a != Rational::integer(1) {
+            return None;
+        }
+
+        let rest = |m: &Monomial| {
+            let mut m = m.clone();
+            m.remove(&Factor::Variable(x));
+            m
+        };
+        let key = rest(monomial);
+
+        // Coefficients of the powers of x, in the terms sharing the rest of the monomial.
+        let mut coefficients = Vec::new();
+        let mut remaining = self.clone();
+        for (m, &coefficient) in &self.terms {
+            if rest(m) == key {
+                let power = m.get(&Factor::Variable(x)).copied().unwrap_or(0) as usize;
+                if coefficients.len() <= power {
+                    coefficients.resize(power + 1, Rational::integer(0));
+                }
+                coefficients[power] = coefficient;
+                remaining.terms.remove(m);
+            }
+        }
+        if coefficients.len() < 2 {
+            return None;
+        }
+
+        // Divide by (x - root), with root = -(c + 1).
+        let root = c.add(Rational::integer(1))?.neg()?;
+        let mut quotient = vec![Rational::integer(0); coefficients.len() - 1];
+        let mut carry = Rational::integer(0);
+        for power in (1..coefficients.len()).rev() {
+            carry = coefficients[power].add(carry.mul(root)?)?;
+            quotient[power - 1] = carry;
+        }
+        if !coefficients[0].add(carry.mul(root)?)?.is_zero() {
+            return None;
+        }
+
+        let mut base = key;
+        base.remove(factor);
+        base.insert(Factor::Factorial(argument.add(&Polynomial::from(1))?), 1);
+        for (power, &coefficient) in quotient.iter().enumerate() {
+            let mut m = base.clone();
+            if power > 0 {
+                m.insert(Factor::Variable(x), power as u32);
+            }
+            remaining = remaining.add(&Polynomial::monomial(m, coefficient))?;
+        }
+        Some(remaining)
+    }
+}
+
+fn variable_name(variable: usize) -> String {
+    if variable < FIRST_FRESH_VARIABLE {
+        Register(variable).to_string()
+    } else {
+        format!("v{}", variable)
+    }
+}
+
+impl std::fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        if self.terms.is_empty() {
+            return write!(f, "0");
+        }
+
+        // Factorials first, then by decreasing degree.
+        let mut terms: Vec<(&Monomial, &Rational)> = self.terms.iter().collect();
+        terms.sort_by_key(|&(m, _)| {
+            let factorials = m
+                .keys()
+                .filter(|f| matches!(f, Factor::Factorial(_)))
+                .count();
+            let degree: u32 = m.values().sum();
+            (std::cmp::Reverse(factorials), std::cmp::Reverse(degree))
+        });
+
+        for (idx, (monomial, coefficient)) in terms.into_iter().enumerate() {
+            if coefficient.numerator < 0 {
+                write!(f, "{}", if idx == 0 { "-" } else { " - " })?;
+            } else if idx > 0 {
+                write!(f, " + ")?;
+            }
+
+            let numerator = coefficient.numerator.unsigned_abs();
+            let denominator = coefficient.denominator;
+            if denominator != 1 {
+                let magnitude = format!("{}/{}", numerator, denominator);
+                if monomial.is_empty() {
+                    write!(f, "{}", magnitude)?;
+                } else {
+                    write!(f, "({})", magnitude)?;
+                }
+            } else if monomial.is_empty() || numerator != 1 {
+                write!(f, "{}", numerator)?;
+            }
+
+            for (factor, &exponent) in monomial {
+                match factor {
+                    Factor::Variable(v) => write!(f, "{}", variable_name(*v))?,
+                    Factor::Factorial(p) => match p.single_variable() {
+                        Some(v) if *p == Polynomial::variable(v) => {
+                            write!(f, "{}!", variable_name(v))?
+                        }
+                        _ => write!(f, "({})!", p)?,
+                    },
+                }
+                if exponent > 1 {
+                    write!(f, "^{}", exponent)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A condition on the initial values of the registers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    Zero(Polynomial),
+    NonZero(Polynomial),
+    NonNegative(Polynomial),
+}
+
+impl Condition {
+    /// `(variable, bound, lower)` if the condition is `variable >= bound` (`lower`) or
+    /// `variable <= bound`.
+    fn bound(&self) -> Option<(usize, i128, bool)> {
+        let p = match self {
+            Condition::NonNegative(p) => p,
+            _ => return None,
+        };
+        let v = p.single_variable()?;
+        let (a, b) = p.linear(v)?;
+        let bound = b.neg()?.mul(a.inverse()?)?;
+        match a.numerator.cmp(&0) {
+            Ordering::Greater => Some((v, bound.ceil(), true)),
+            Ordering::Less => Some((v, bound.floor(), false)),
+            Ordering::Equal => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        if let Some((v, bound, lower)) = self.bound() {
+            let relation = if lower { ">=" } else { "<=" };
+            return write!(f, "{} {} {}", variable_name(v), relation, bound);
+        }
+
+        match self {
+            Condition::Zero(p) => write!(f, "{} = 0", p),
+            Condition::NonZero(p) => write!(f, "{} != 0", p),
+            Condition::NonNegative(p) => write!(f, "{} >= 0", p),
+        }
+    }
+}
+
+/// Result of `Computer::evaluate_symbolically`.
+#[derive(Debug, Clone)]
+pub struct Summary {
+    registers: Vec<(Register, Polynomial)>,
+    output: Vec<Polynomial>,
+    assumptions: Vec<Condition>,
+}
+
+impl Summary {
+    /// Final value of every register, in the order they were declared in.
+    pub fn registers(&self) -> &[(Register, Polynomial)] {
+        &self.registers
+    }
+
+    pub fn register(&self, register: Register) -> Option<&Polynomial> {
+        self.registers
+            .iter()
+            .find(|(r, _)| *r == register)
+            .map(|(_, p)| p)
+    }
+
+    pub fn output(&self) -> &[Polynomial] {
+        &self.output
+    }
+
+    /// Conditions under which the result holds, besides loops terminating.
+    pub fn assumptions(&self) -> &[Condition] {
+        &self.assumptions
+    }
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        for (register, value) in &self.registers {
+            writeln!(f, "{} = {}", register, value)?;
+        }
+        if !self.output.is_empty() {
+            let output: Vec<String> = self.output.iter().map(|p| p.to_string()).collect();
+            writeln!(f, "output {}", output.join(", "))?;
+        }
+        for condition in &self.assumptions {
+            writeln!(f, "assuming {}", condition)?;
+        }
+        Ok(())
+    }
+}
+
+/// Keep only the tightest bound on each variable, and drop duplicates.
+fn merge(assumptions: Vec<Condition>) -> Vec<Condition> {
+    let mut bounds: BTreeMap<(usize, bool), (i128, Condition)> = BTreeMap::new();
+    let mut others = Vec::new();
+    for condition in assumptions {
+        match condition.bound() {
+            Some((v, bound, lower)) => {
+                let best = bounds
+                    .entry((v, lower))
+                    .or_insert_with(|| (bound, condition.clone()));
+                if (lower && bound > best.0) || (!lower && bound < best.0) {
+                    *best = (bound, condition);
+                }
+            }
+            None if !others.contains(&condition) => others.push(condition),
+            None => (),
+        }
+    }
+
+    let mut result: Vec<Condition> = bounds.into_values().map(|(_, c)| c).collect();
+    result.extend(others);
+    result
+}
+
+fn unsupported(instruction_pointer: i32, reason: &'static str) -> ComputerError {
+    ComputerError::NotSymbolic {
+        instruction_pointer,
+        reason,
+    }
+}
+
+/// The result of polynomial arithmetic, or an error if a coefficient overflowed.
+fn checked<T>(value: Option<T>, instruction_pointer: i32) -> ComputerResult<T> {
+    value.ok_or_else(|| unsupported(instruction_pointer, "coefficient overflow"))
+}
+
+/// A loop whose body is being evaluated from fresh variables.
+struct Body<'a> {
+    header: i32,
+    back_edge: i32,
+    /// Variable standing for the value of register `r` at the header: `first_variable + r`.
+    first_variable: usize,
+    /// Values of the registers when the loop is reached.
+    entry: &'a [Polynomial],
+    /// Conditions for the body to take the same path again.
+    guards: Vec<Condition>,
+}
+
+impl<'a> Body<'a> {
+    fn is_fresh(&self, variable: usize) -> bool {
+        (self.first_variable..self.first_variable + self.entry.len()).contains(&variable)
+    }
+
+    /// Value of a polynomial of the fresh variables when the loop is reached.
+    fn at_entry(&self, p: &Polynomial, instruction_pointer: i32) -> ComputerResult<Polynomial> {
+        p.substitute(&|v| {
+            if self.is_fresh(v) {
+                self.entry[v - self.first_variable].clone()
+            } else {
+                Polynomial::variable(v)
+            }
+        })
+        .ok_or_else(|| unsupported(instruction_pointer, "factorial out of range"))
+    }
+}
+
+struct Evaluator {
+    program: Vec<Instruction>,
+    next_variable: usize,
+    steps: usize,
+    assumptions: Vec<Condition>,
+    output: Vec<Polynomial>,
+}
+
+impl Evaluator {
+    fn value(registers: &[Polynomial], argument: Argument) -> Polynomial {
+        match argument {
+            Argument::Value(Value(v)) => Polynomial::from(v),
+            Argument::Register(Register(r)) => registers[r].clone(),
+        }
+    }
+
+    /// Run from `instruction_pointer` until the program halts or, when evaluating the body of a
+    /// loop, until it jumps back to the header.
+    fn execute(
+        &mut self,
+        registers: &mut [Polynomial],
+        instruction_pointer: &mut i32,
+        mut body: Option<&mut Body>,
+    ) -> ComputerResult<()> {
+        loop {
+            let ip = *instruction_pointer;
+            if let Some(body) = &body {
+                if ip < body.header || ip > body.back_edge {
+                    return Err(unsupported(ip, "jump out of a loop"));
+                }
+            }
+
+            let i = match self.program.get(ip as usize) {
+                Some(&i) => i,
+                None => return Ok(()),
+            };
+
+            self.steps += 1;
+            if self.steps > MAX_STEPS {
+                return Err(ComputerError::StepLimitExceeded(MAX_STEPS));
+            }
+
+            match i {
+                Instruction::Copy(a, Argument::Register(Register(r))) => {
+                    registers[r] = Evaluator::value(registers, a);
+                }
+                Instruction::Increment(Argument::Register(Register(r))) => {
+                    registers[r] = checked(registers[r].add(&Polynomial::from(1)), ip)?;
+                }
+                Instruction::Decrement(Argument::Register(Register(r))) => {
+                    registers[r] = checked(registers[r].sub(&Polynomial::from(1)), ip)?;
+                }
+                Instruction::Toggle(a) => {
+                    let target =
+                        Evaluator::value(registers, a).add(&Polynomial::from(i128::from(ip)));
+                    let target = checked(target, ip)?;
+                    if let Some(t) = self.toggle_target(&target, ip, body.as_deref_mut())? {
+                        if body.is_some() {
+                            return Err(unsupported(ip, "tgl inside a loop"));
+                        }
+                        self.program[t] = self.program[t].toggle();
+                    }
+                }
+                Instruction::In(_) => return Err(unsupported(ip, "in")),
+                Instruction::Out(a) => {
+                    if body.is_some() {
+                        return Err(unsupported(ip, "out inside a loop"));
+                    }
+                    self.output.push(Evaluator::value(registers, a));
+                }
+                Instruction::JumpIfNotZero(a, b) => {
+                    let condition = Evaluator::value(registers, a);
+                    let offset = Evaluator::value(registers, b).as_constant();
+
+                    if let Some(body) = body.as_deref_mut() {
+                        if ip == body.back_edge {
+                            if condition.as_constant() == Some(0) {
+                                return Err(unsupported(ip, "loop exits"));
+                            } else if offset != Some(i128::from(body.header - ip)) {
+                                return Err(unsupported(ip, "loop jumps elsewhere"));
+                            }
+                            if condition.as_constant().is_none() {
+                                body.guards.push(Condition::NonZero(condition));
+                            }
+                            return Ok(());
+                        }
+                    }
+
+                    // Loops are assumed to run more than once, so a backward jump is taken.
+                    let constant = condition.as_constant();
+                    let taken = match (constant, offset) {
+                        (Some(c), _) => c != 0,
+                        (None, Some(offset)) if offset <= 0 => true,
+                        (None, _) => self.is_nonzero(&condition, ip, body.as_deref_mut())?,
+                    };
+
+                    if taken {
+                        let offset =
+                            offset.ok_or_else(|| unsupported(ip, "jump by a variable offset"))?;
+                        let target = offset.saturating_add(ip.into());
+                        *instruction_pointer = match i32::try_from(target) {
+                            Ok(target) if target >= 0 => target,
+                            _ => return Err(ComputerError::InvalidInstructionPointer(target)),
+                        };
+
+                        if *instruction_pointer <= ip {
+                            let top = body.is_none();
+                            match self.accelerate(registers, *instruction_pointer, ip, top) {
+                                Err(ComputerError::NotSymbolic { .. }) if constant.is_some() => (),
+                                result => result?,
+                            }
+                        }
+                        continue;
+                    }
+                }
+                // Instructions with a value where a register is expected do nothing.
+                _ => (),
+            }
+
+            *instruction_pointer += 1;
+        }
+    }
+
+    /// Decide whether `condition` is not zero, the way it is for large initial values.
+    fn is_nonzero(
+        &mut self,
+        condition: &Polynomial,
+        ip: i32,
+        body: Option<&mut Body>,
+    ) -> ComputerResult<bool> {
+        let sign = |p: &Polynomial| match p.asymptotic_sign() {
+            Some(Ordering::Equal) | None => Err(unsupported(ip, "jump depends on the data")),
+            Some(_) => Ok(true),
+        };
+
+        match body {
+            None => {
+                let taken = sign(condition)?;
+                self.assumptions.push(Condition::NonZero(condition.clone()));
+                Ok(taken)
+            }
+            Some(body) => {
+                let at_entry = body.at_entry(condition, ip)?;
+                let taken = match at_entry.as_constant() {
+                    Some(c) => c != 0,
+                    None => sign(&at_entry)?,
+                };
+                body.guards.push(if taken {
+                    Condition::NonZero(condition.clone())
+                } else {
+                    Condition::Zero(condition.clone())
+                });
+                Ok(taken)
+            }
+        }
+    }
+
+    /// The address toggled by `tgl` with this absolute `target`, if it is inside the program.
+    fn toggle_target(
+        &mut self,
+        target: &Polynomial,
+        ip: i32,
+        body: Option<&mut Body>,
+    ) -> ComputerResult<Option<usize>> {
+        let len = self.program.len() as i128;
+        if let Some(t) = target.as_constant() {
+            return Ok(usize::try_from(t).ok().filter(|&t| t < self.program.len()));
+        }
+
+        let outside = |above: bool| {
+            let distance = if above {
+                target.sub(&Polynomial::from(len))
+            } else {
+                target
+                    .scale(Rational::integer(-1))
+                    .and_then(|p| p.sub(&Polynomial::from(1)))
+            };
+            Ok(Condition::NonNegative(checked(distance, ip)?))
+        };
+        let above = |p: &Polynomial| match p.asymptotic_sign() {
+            Some(Ordering::Greater) => Ok(true),
+            Some(Ordering::Less) => Ok(false),
+            _ => Err(unsupported(ip, "tgl target depends on the data")),
+        };
+
+        match body {
+            None => {
+                let condition = outside(above(target)?)?;
+                self.assumptions.push(condition);
+                Ok(None)
+            }
+            Some(body) => {
+                let at_entry = body.at_entry(target, ip)?;
+                let condition = match at_entry.as_constant() {
+                    Some(t) if 0 <= t && t < len => return Ok(Some(t as usize)),
+                    Some(t) => outside(t >= len)?,
+                    None => outside(above(&at_entry)?)?,
+                };
+                body.guards.push(condition);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Apply as many iterations as possible of the loop from `header` to `back_edge` at once,
+    /// leaving the rest to normal evaluation. `top` is false when `registers` are themselves the
+    /// fresh variables of an enclosing loop.
+    fn accelerate(
+        &mut self,
+        registers: &mut [Polynomial],
+        header: i32,
+        back_edge: i32,
+        top: bool,
+    ) -> ComputerResult<()> {
+        let n = registers.len();
+        let first_variable = self.next_variable;
+        self.next_variable += n;
+        let fresh = |r: usize| Polynomial::variable(first_variable + r);
+
+        let entry = registers.to_vec();
+        let mut body = Body {
+            header,
+            back_edge,
+            first_variable,
+            entry: &entry,
+            guards: Vec::new(),
+        };
+        let mut after: Vec<Polynomial> = (0..n).map(fresh).collect();
+        let mut ip = header;
+        self.execute(&mut after, &mut ip, Some(&mut body))?;
+
+        let modified: Vec<usize> = (0..n).filter(|&r| after[r] != fresh(r)).collect();
+        let invariant = |p: &Polynomial| modified.iter().all(|&r| !p.mentions(first_variable + r));
+
+        // The counter moves by one, and decides when the loop stops taking the same path.
+        let counter = modified.iter().copied().find(|&r| {
+            let step = after[r].sub(&fresh(r)).and_then(|p| p.as_constant());
+            (step == Some(1) || step == Some(-1))
+                && body.guards.iter().any(|g| match g {
+                    Condition::Zero(p) | Condition::NonZero(p) | Condition::NonNegative(p) => {
+                        p.mentions(first_variable + r)
+                    }
+                })
+        });
+        let k = counter.ok_or_else(|| unsupported(back_edge, "no loop counter"))?;
+        let k_variable = first_variable + k;
+
+        // Work with a counter going down: `sign * counter`.
+        let step = after[k].sub(&fresh(k)).and_then(|p| p.as_constant());
+        let sign = -step.unwrap_or_default();
+        let start = checked(entry[k].scale(Rational::integer(sign)), back_edge)?;
+
+        // The loop takes the same path as long as `sign * counter >= threshold`.
+        let mut threshold = None;
+        for guard in &body.guards {
+            let p = match guard {
+                Condition::Zero(p) | Condition::NonZero(p) | Condition::NonNegative(p) => p,
+            };
+            if !p.mentions(k_variable) {
+                if !invariant(p) {
+                    return Err(unsupported(back_edge, "loop condition depends on the data"));
+                }
+                let at_entry = body.at_entry(p, back_edge)?;
+                if top && at_entry.as_constant().is_none() {
+                    self.assumptions.push(match guard {
+                        Condition::Zero(_) => Condition::Zero(at_entry),
+                        Condition::NonZero(_) => Condition::NonZero(at_entry),
+                        Condition::NonNegative(_) => Condition::NonNegative(at_entry),
+                    });
+                }
+                continue;
+            }
+
+            let (a, b) = p
+                .linear(k_variable)
+                .ok_or_else(|| unsupported(back_edge, "loop condition depends on the data"))?;
+            let a = checked(a.mul(Rational::integer(sign)), back_edge)?;
+            let bound = checked(b.neg().and_then(|b| b.mul(a.inverse()?)), back_edge)?;
+            let m = match guard {
+                Condition::Zero(_) => return Err(unsupported(back_edge, "loop runs only once")),
+                Condition::NonZero(_) => match bound.to_integer() {
+                    Some(root) if start.as_constant().is_none_or(|s| s >= root) => {
+                        checked(root.checked_add(1), back_edge)?
+                    }
+                    _ => continue,
+                },
+                Condition::NonNegative(_) if a.numerator > 0 => bound.ceil(),
+                Condition::NonNegative(_) => continue,
+            };
+            threshold = Some(threshold.map_or(m, |t: i128| t.max(m)));
+        }
+        let threshold = threshold.ok_or_else(|| unsupported(back_edge, "loop never exits"))?;
+
+        let last = checked(threshold.checked_sub(1), back_edge)?;
+        let iterations = checked(start.sub(&Polynomial::from(last)), back_edge)?;
+        if iterations.as_constant().is_some_and(|n| n <= 0) {
+            return Ok(());
+        }
+        let exit = checked(last.checked_mul(sign), back_edge)?;
+
+        let at_entry = |p: &Polynomial| body.at_entry(p, back_edge);
+        let mut result = entry.clone();
+        for &r in &modified {
+            let s = first_variable + r;
+            let delta = checked(after[r].sub(&fresh(r)), back_edge)?;
+            result[r] = if r == k {
+                Polynomial::from(exit)
+            } else if invariant(&delta) {
+                let increment = checked(iterations.mul(&at_entry(&delta)?), back_edge)?;
+                checked(entry[r].add(&increment), back_edge)?
+            } else if invariant(&after[r]) {
+                at_entry(&after[r])?
+            } else {
+                // Multiplied by `counter + c` on every iteration: the product of `start + c` down
+                // to `exit + 1 + c`.
+                let c = after[r]
+                    .split(s)
+                    .filter(|(_, rest)| sign == 1 && *rest == Polynomial::default())
+                    .and_then(|(q, _)| q.sub(&Polynomial::variable(k_variable))?.as_constant())
+                    .ok_or_else(|| unsupported(back_edge, "loop is too complex"))?;
+                let start = checked(entry[k].add(&Polynomial::from(c)), back_edge)?;
+                let product = Polynomial::factorial(start);
+                let divisor = checked(exit.checked_add(c), back_edge)?;
+                let divisor = Polynomial::factorial(Polynomial::from(divisor))
+                    .and_then(|d| d.as_constant())
+                    .filter(|&d| d != 0);
+                match (product, divisor) {
+                    (Some(product), Some(divisor)) => checked(
+                        entry[r]
+                            .mul(&product)
+                            .and_then(|p| p.scale(Rational::new(1, divisor)?)),
+                        back_edge,
+                    )?,
+                    _ => return Err(unsupported(back_edge, "factorial out of range")),
+                }
+            };
+        }
+
+        registers.clone_from_slice(&result);
+        Ok(())
+    }
+}
+
+impl Computer {
+    /// Evaluate the program from the current state, with the given registers standing for
+    /// unknown values, which the final value of every register is expressed in.
+    ///
+    /// Returns `ComputerError::NotSymbolic` if a `tgl` or a jump depends on the data in a way that
+    /// cannot be summarized (see the `symbolic` module), or `ComputerError::StepLimitExceeded` if
+    /// the program does not halt soon enough.
+    pub fn evaluate_symbolically(&self, symbols: &[Register]) -> ComputerResult<Summary> {
+        if let Some(r) = symbols.iter().find(|r| !self.register_names.contains(r)) {
+            return Err(ComputerError::InvalidRegister(r.to_string()));
+        }
+
+        let mut registers: Vec<Polynomial> = self
+            .registers
+            .iter()
+            .enumerate()
+            .map(|(r, &Value(v))| {
+                if symbols.contains(&Register(r)) {
+                    Polynomial::variable(r)
+                } else {
+                    Polynomial::from(v)
+                }
+            })
+            .collect();
+
+        let mut evaluator = Evaluator {
+            program: self.program.clone(),
+            next_variable: FIRST_FRESH_VARIABLE,
+            steps: 0,
+            assumptions: Vec::new(),
+            output: Vec::new(),
+        };
+        let mut ip = self.instruction_pointer;
+        evaluator.execute(&mut registers, &mut ip, None)?;
+
+        Ok(Summary {
+            registers: self
+                .register_names
+                .iter()
+                .map(|&r| (r, registers[r.0].simplify()))
+                .collect(),
+            output: evaluator.output.iter().map(Polynomial::simplify).collect(),
+            assumptions: merge(evaluator.assumptions),
+        })
+    }
+}
@@ -1,55 +1,463 @@
-use std::collections::HashMap;
-use std::hash::Hash;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryInto;
 use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::ops::{Add, BitAnd, BitOr, Sub};
+use std::sync::Arc;
+use storage::{ArrayMap, Storage};
 
-#[derive(Clone, Debug, Default)]
-pub struct Counter<K: Eq + Hash>(HashMap<K, usize>);
+pub mod column;
+#[cfg(feature = "rayon")]
+mod parallel;
+pub mod sketch;
+pub mod storage;
 
-impl<K: Eq + Hash> Counter<K> {
+type Comparator<K> = Arc<dyn Fn(&K, &K) -> Ordering + Send + Sync>;
+
+/// How elements with the same count are ranked.
+#[derive(Default)]
+pub enum TieBreak<K> {
+    /// The element counted first ranks first. This is the default.
+    #[default]
+    Insertion,
+    /// The element that compares less ranks first.
+    By(Comparator<K>),
+}
+
+impl<K> TieBreak<K> {
+    /// Rank elements with the same count using a custom comparator.
+    pub fn by<F>(compare: F) -> Self
+    where
+        F: Fn(&K, &K) -> Ordering + Send + Sync + 'static,
+    {
+        TieBreak::By(Arc::new(compare))
+    }
+}
+
+impl<K: Ord + 'static> TieBreak<K> {
+    /// Rank elements with the same count in ascending order.
+    pub fn key() -> Self {
+        TieBreak::by(K::cmp)
+    }
+}
+
+impl<K> Clone for TieBreak<K> {
+    fn clone(&self) -> Self {
+        match self {
+            TieBreak::Insertion => TieBreak::Insertion,
+            TieBreak::By(compare) => TieBreak::By(Arc::clone(compare)),
+        }
+    }
+}
+
+impl<K> std::fmt::Debug for TieBreak<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            TieBreak::Insertion => write!(f, "Insertion"),
+            TieBreak::By(_) => write!(f, "By(..)"),
+        }
+    }
+}
+
+/// A type that counts can have: unsigned integers, or signed integers and
+/// floats for counts that can be negative or weighted.
+pub trait Count: Copy + PartialOrd + Add<Output = Self> {
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// `self - other`, which stops at 0 for unsigned integers.
+    fn minus(self, other: Self) -> Self;
+
+    /// A total order on counts, which for floats puts NaN above infinity
+    /// (or below negative infinity, for a negative NaN).
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+
+macro_rules! count_integer {
+    ($($t:ty)*) => {$(
+        impl Count for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            fn minus(self, other: Self) -> Self {
+                self.saturating_sub(other)
+            }
+
+            fn total_cmp(&self, other: &Self) -> Ordering {
+                self.cmp(other)
+            }
+        }
+    )*};
+}
+
+count_integer!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+impl Count for f32 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn minus(self, other: Self) -> Self {
+        self - other
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f32::total_cmp(self, other)
+    }
+}
+
+impl Count for f64 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn minus(self, other: Self) -> Self {
+        self - other
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f64::total_cmp(self, other)
+    }
+}
+
+/// What exact counters and approximate sketches have in common.
+pub trait Frequencies<K> {
+    type Count;
+
+    /// Count `element` once.
+    fn add(&mut self, element: K);
+
+    /// The count of `element`, or an estimate of it.
+    fn get(&self, element: &K) -> Self::Count;
+
+    /// The `n` most common elements and their counts, from the most common.
+    fn most_common_n(&self, n: usize) -> Vec<(&K, Self::Count)>;
+}
+
+impl<K, N: Count, S: Storage<K, N>> Frequencies<K> for Counter<K, N, S> {
+    type Count = N;
+
+    fn add(&mut self, element: K) {
+        Counter::add(self, element);
+    }
+
+    fn get(&self, element: &K) -> N {
+        Counter::get(self, element)
+    }
+
+    fn most_common_n(&self, n: usize) -> Vec<(&K, N)> {
+        Counter::most_common_n(self, n)
+    }
+}
+
+/// The count of an element, and when it was first counted.
+#[derive(Clone, Copy, Debug)]
+pub struct Entry<N> {
+    count: N,
+    rank: usize,
+}
+
+/// Counts of elements, kept in a `HashMap` by default.
+#[derive(Clone, Debug)]
+pub struct Counter<K, N = usize, S = HashMap<K, Entry<N>>> {
+    entries: S,
+    inserted: usize,
+    tie_break: TieBreak<K>,
+    count: PhantomData<fn() -> N>,
+}
+
+/// A counter that iterates in key order.
+pub type OrderedCounter<K, N = usize> = Counter<K, N, BTreeMap<K, Entry<N>>>;
+
+/// A counter for keys from a small alphabet, such as bytes or ASCII
+/// characters, which iterates in key order.
+pub type ArrayCounter<K, N = usize> = Counter<K, N, ArrayMap<K, N>>;
+
+impl<K, N: Count, S: Storage<K, N>> Default for Counter<K, N, S> {
+    fn default() -> Self {
+        Counter::new()
+    }
+}
+
+impl<K, N: Count, S: Storage<K, N>> Counter<K, N, S> {
     /// Create a new Counter.
     pub fn new() -> Self {
-        Counter(HashMap::new())
+        Counter {
+            entries: S::default(),
+            inserted: 0,
+            tie_break: TieBreak::Insertion,
+            count: PhantomData,
+        }
+    }
+
+    /// Set how elements with the same count are ranked.
+    pub fn set_tie_break(&mut self, tie_break: TieBreak<K>) -> &mut Self {
+        self.tie_break = tie_break;
+        self
     }
 
     /// Increment the counter for `element`.
     pub fn add(&mut self, element: K) {
-        self.0.entry(element).and_modify(|e| *e += 1).or_insert(1);
+        self.add_count(element, N::ONE);
     }
 
-    /// Return a reference to the most common element. In case of a tie of two
-    /// (or more) elements, an arbitrary element is returned.
+    /// Add `n` to the counter for `element`.
+    pub fn add_count(&mut self, element: K, n: N) {
+        let count = self.count_mut(element);
+        *count = *count + n;
+    }
+
+    /// Add the counts of `other` to those of this counter. Elements that
+    /// were not counted yet rank after the others in case of ties.
+    pub fn merge(&mut self, other: Self) {
+        for (element, n) in other {
+            self.add_count(element, n);
+        }
+    }
+
+    /// Count every element of `elements`.
+    pub fn update<I: IntoIterator<Item = K>>(&mut self, elements: I) {
+        for element in elements {
+            self.add(element);
+        }
+    }
+
+    /// Uncount every element of `elements`. Unsigned counts stop at 0, signed
+    /// ones go negative, and elements whose count reaches 0 are removed.
+    pub fn subtract<I: IntoIterator<Item = K>>(&mut self, elements: I) {
+        let uncounted = N::ZERO.minus(N::ONE);
+        for element in elements {
+            match self.entries.get_mut(&element) {
+                Some(entry) => {
+                    entry.count = entry.count.minus(N::ONE);
+                    if entry.count == N::ZERO {
+                        self.entries.remove(&element);
+                    }
+                }
+                None if uncounted != N::ZERO => *self.count_mut(element) = uncounted,
+                None => (),
+            }
+        }
+    }
+
+    /// The count of `element`, which is inserted with a count of 0 if need be.
+    fn count_mut(&mut self, element: K) -> &mut N {
+        let rank = self.inserted;
+        let entry = self.entries.get_or_insert(
+            element,
+            Entry {
+                count: N::ZERO,
+                rank,
+            },
+        );
+        if entry.rank == rank {
+            self.inserted += 1;
+        }
+        &mut entry.count
+    }
+
+    /// Remove the elements whose count is not positive.
+    fn retain_positive(&mut self) {
+        self.entries.retain(|_, entry| entry.count > N::ZERO);
+    }
+
+    /// The count of `element`, which is 0 if it was never counted.
+    pub fn get(&self, element: &K) -> N {
+        self.entries.get(element).map_or(N::ZERO, |e| e.count)
+    }
+
+    /// The sum of all counts.
+    pub fn total(&self) -> N {
+        self.iter().fold(N::ZERO, |total, (_, n)| total + n)
+    }
+
+    /// The number of distinct elements.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing was counted.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the elements and their counts, in arbitrary order for a
+    /// `Counter` and in key order for the others.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, N)> {
+        self.entries.iter().map(|(k, e)| (k, e.count))
+    }
+
+    /// Return a reference to the most common element. Ties are broken
+    /// according to the counter's `TieBreak`.
     ///
     /// Returns `None` if the counter is empty.
     pub fn most_common(&self) -> Option<&K> {
-        self.0.iter().max_by_key(|p| p.1).map(|(k, _)| k)
+        self.entries
+            .iter()
+            .min_by(|a, b| self.compare(a, b, true))
+            .map(|(k, _)| k)
     }
 
-    /// Return a reference to the least common element. In case of a tie of two
-    /// (or more) elements, an arbitrary element is returned.
+    /// Return a reference to the least common element. Ties are broken
+    /// according to the counter's `TieBreak`.
     ///
     /// Returns `None` if the counter is empty.
     pub fn least_common(&self) -> Option<&K> {
-        self.0.iter().min_by_key(|p| p.1).map(|(k, _)| k)
+        self.entries
+            .iter()
+            .min_by(|a, b| self.compare(a, b, false))
+            .map(|(k, _)| k)
+    }
+
+    /// The `n` most common elements and their counts, from the most common.
+    pub fn most_common_n(&self, n: usize) -> Vec<(&K, N)> {
+        self.ranked(n, true)
+    }
+
+    /// The `n` least common elements and their counts, from the least common.
+    pub fn least_common_n(&self, n: usize) -> Vec<(&K, N)> {
+        self.ranked(n, false)
+    }
+
+    fn ranked(&self, n: usize, descending: bool) -> Vec<(&K, N)> {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        let compare = |a: &(&K, &Entry<N>), b: &(&K, &Entry<N>)| self.compare(a, b, descending);
+        if n < entries.len() {
+            entries.select_nth_unstable_by(n, compare);
+            entries.truncate(n);
+        }
+        entries.sort_unstable_by(compare);
+
+        entries.into_iter().map(|(k, e)| (k, e.count)).collect()
+    }
+
+    /// Order two entries by count, following `Count::total_cmp`, the one that
+    /// ranks first being less.
+    fn compare(&self, a: &(&K, &Entry<N>), b: &(&K, &Entry<N>), descending: bool) -> Ordering {
+        let by_count = if descending {
+            b.1.count.total_cmp(&a.1.count)
+        } else {
+            a.1.count.total_cmp(&b.1.count)
+        };
+
+        by_count.then_with(|| match &self.tie_break {
+            TieBreak::Insertion => a.1.rank.cmp(&b.1.rank),
+            TieBreak::By(compare) => compare(a.0, b.0),
+        })
     }
 }
 
-impl<K: Eq + Hash> FromIterator<K> for Counter<K> {
+impl<K, N, S> Counter<K, N, S>
+where
+    N: Count + TryInto<usize>,
+    S: Storage<K, N>,
+{
+    /// Iterate over the elements, each repeated as many times as it was
+    /// counted, in the same order as `iter`. Elements with a negative count
+    /// are skipped.
+    pub fn elements(&self) -> impl Iterator<Item = &K> {
+        self.iter()
+            .flat_map(|(k, n)| std::iter::repeat_n(k, n.try_into().unwrap_or(0)))
+    }
+}
+
+impl<K, N: Count, S: Storage<K, N>> FromIterator<K> for Counter<K, N, S> {
     fn from_iter<T: IntoIterator<Item = K>>(iter: T) -> Self {
-        let mut h = HashMap::new();
+        let mut counter = Counter::new();
+        counter.update(iter);
+        counter
+    }
+}
+
+impl<K, N: Count, S: Storage<K, N>> Extend<K> for Counter<K, N, S> {
+    fn extend<T: IntoIterator<Item = K>>(&mut self, iter: T) {
+        self.update(iter);
+    }
+}
+
+/// The sum of the counts of each element, keeping only those that are
+/// positive.
+impl<K, N: Count, S: Storage<K, N>> Add for Counter<K, N, S> {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self.merge(other);
+        self.retain_positive();
+        self
+    }
+}
 
-        for item in iter {
-            h.entry(item).and_modify(|e| *e += 1).or_insert(1);
+/// The difference of the counts of each element, keeping only those that
+/// remain positive.
+impl<K, N: Count, S: Storage<K, N>> Sub for Counter<K, N, S> {
+    type Output = Self;
+
+    fn sub(mut self, other: Self) -> Self {
+        for (element, n) in other {
+            if let Some(entry) = self.entries.get_mut(&element) {
+                entry.count = entry.count.minus(n);
+            }
+        }
+        self.retain_positive();
+        self
+    }
+}
+
+/// The maximum of the counts of each element, keeping only those that are
+/// positive.
+impl<K, N: Count, S: Storage<K, N>> BitOr for Counter<K, N, S> {
+    type Output = Self;
+
+    fn bitor(mut self, other: Self) -> Self {
+        for (element, n) in other {
+            let count = self.count_mut(element);
+            if n > *count {
+                *count = n;
+            }
         }
+        self.retain_positive();
+        self
+    }
+}
 
-        Counter(h)
+/// The minimum of the counts of each element, keeping only those that are
+/// positive in both.
+impl<K, N: Count, S: Storage<K, N>> BitAnd for Counter<K, N, S> {
+    type Output = Self;
+
+    fn bitand(mut self, other: Self) -> Self {
+        self.entries.retain(|element, entry| {
+            let n = other.get(element);
+            if n < entry.count {
+                entry.count = n;
+            }
+            entry.count > N::ZERO
+        });
+        self
     }
 }
 
-impl<K: Eq + Hash> IntoIterator for Counter<K> {
-    type Item = (K, usize);
-    type IntoIter = std::collections::hash_map::IntoIter<K, usize>;
+impl<K, N: Count, S: Storage<K, N>> IntoIterator for Counter<K, N, S> {
+    type Item = (K, N);
+    type IntoIter = IntoIter<S::IntoIter>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+        IntoIter(self.entries.into_iter())
+    }
+}
+
+/// An iterator over the elements of a counter and their counts, in the same
+/// order as `Counter::iter`.
+#[derive(Debug)]
+pub struct IntoIter<I>(I);
+
+impl<K, N, I: Iterator<Item = (K, Entry<N>)>> Iterator for IntoIter<I> {
+    type Item = (K, N);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, e)| (k, e.count))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
     }
 }
@@ -0,0 +1,85 @@
+//! Counting the elements of rows column by column, such as the characters of
+//! lines of text.
+
+use super::storage::Storage;
+use super::{Count, Counter};
+use std::iter::FromIterator;
+
+/// One counter per column of the rows it is given. Rows can have different
+/// lengths.
+#[derive(Clone, Debug)]
+pub struct ColumnCounter<C = Counter<char>> {
+    columns: Vec<C>,
+}
+
+impl<C> Default for ColumnCounter<C> {
+    fn default() -> Self {
+        ColumnCounter {
+            columns: Vec::new(),
+        }
+    }
+}
+
+impl<K, N: Count, S: Storage<K, N>> ColumnCounter<Counter<K, N, S>> {
+    /// Create a new ColumnCounter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count every element of `row` in its column.
+    pub fn add_row<I: IntoIterator<Item = K>>(&mut self, row: I) {
+        for (idx, element) in row.into_iter().enumerate() {
+            if idx == self.columns.len() {
+                self.columns.push(Counter::new());
+            }
+            self.columns[idx].add(element);
+        }
+    }
+
+    /// Add the counts of `other` to those of this counter, column by column.
+    pub fn merge(&mut self, other: Self) {
+        for (idx, column) in other.columns.into_iter().enumerate() {
+            match self.columns.get_mut(idx) {
+                Some(counter) => counter.merge(column),
+                None => self.columns.push(column),
+            }
+        }
+    }
+
+    /// The counter of each column.
+    pub fn columns(&self) -> &[Counter<K, N, S>] {
+        &self.columns
+    }
+
+    /// The most common element of each column.
+    pub fn most_common(&self) -> impl Iterator<Item = &K> {
+        self.columns.iter().filter_map(Counter::most_common)
+    }
+
+    /// The least common element of each column.
+    pub fn least_common(&self) -> impl Iterator<Item = &K> {
+        self.columns.iter().filter_map(Counter::least_common)
+    }
+}
+
+impl<N: Count, S: Storage<char, N>> ColumnCounter<Counter<char, N, S>> {
+    /// Count every character of `line` in its column.
+    pub fn add_line(&mut self, line: &str) {
+        self.add_row(line.chars());
+    }
+}
+
+impl<K, N, S, R> FromIterator<R> for ColumnCounter<Counter<K, N, S>>
+where
+    N: Count,
+    S: Storage<K, N>,
+    R: IntoIterator<Item = K>,
+{
+    fn from_iter<T: IntoIterator<Item = R>>(rows: T) -> Self {
+        let mut counter = ColumnCounter::new();
+        for row in rows {
+            counter.add_row(row);
+        }
+        counter
+    }
+}
@@ -0,0 +1,48 @@
+//! Counting from parallel iterators, each thread counting its share of the
+//! elements before the counters are merged. Ties between elements ranked by
+//! insertion are then ranked in no particular order.
+
+use super::column::ColumnCounter;
+use super::storage::Storage;
+use super::{Count, Counter};
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelIterator};
+
+impl<K, N, S> FromParallelIterator<K> for Counter<K, N, S>
+where
+    K: Send,
+    N: Count + Send,
+    S: Storage<K, N> + Send,
+{
+    fn from_par_iter<I: IntoParallelIterator<Item = K>>(elements: I) -> Self {
+        elements
+            .into_par_iter()
+            .fold(Counter::new, |mut counter, element| {
+                counter.add(element);
+                counter
+            })
+            .reduce(Counter::new, |mut counter, other| {
+                counter.merge(other);
+                counter
+            })
+    }
+}
+
+impl<K, N, S, R> FromParallelIterator<R> for ColumnCounter<Counter<K, N, S>>
+where
+    K: Send,
+    N: Count + Send,
+    S: Storage<K, N> + Send,
+    R: IntoIterator<Item = K> + Send,
+{
+    fn from_par_iter<I: IntoParallelIterator<Item = R>>(rows: I) -> Self {
+        rows.into_par_iter()
+            .fold(ColumnCounter::new, |mut counter, row| {
+                counter.add_row(row);
+                counter
+            })
+            .reduce(ColumnCounter::new, |mut counter, other| {
+                counter.merge(other);
+                counter
+            })
+    }
+}
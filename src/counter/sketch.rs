@@ -0,0 +1,271 @@
+//! Approximate counting in bounded memory, for streams with too many distinct elements to count
+//! them all exactly:
+//!
+//! - a `CountMinSketch` estimates the count of any element, never below its true count, and keeps
+//!   track of the elements with the highest estimates;
+//! - a `SpaceSaving` tracker only monitors a fixed number of elements, and is guaranteed to monitor
+//!   every element that makes up more than `1 / capacity` of the stream.
+
+use super::Frequencies;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// A table of `depth` rows of `width` counts, each element incrementing one count per row. The
+/// estimate of an element is the lowest of its counts, which exceeds its true count by at most
+/// `total / width * e` with probability `1 - exp(-depth)`.
+#[derive(Clone, Debug)]
+pub struct CountMinSketch<K> {
+    width: usize,
+    depth: usize,
+    table: Vec<usize>,
+    total: usize,
+    /// The elements with the highest estimates seen so far, and their estimates when last added.
+    top: Vec<(K, usize)>,
+    tracked: usize,
+}
+
+impl<K: Eq + Hash> CountMinSketch<K> {
+    /// Create a sketch with `depth` rows of `width` counts, which keeps track of the `tracked`
+    /// elements with the highest estimates.
+    pub fn new(width: usize, depth: usize, tracked: usize) -> Self {
+        assert!(width > 0 && depth > 0, "a sketch needs at least one count");
+
+        CountMinSketch {
+            width,
+            depth,
+            table: vec![0; width * depth],
+            total: 0,
+            top: Vec::with_capacity(tracked),
+            tracked,
+        }
+    }
+
+    /// Create a sketch whose estimates exceed true counts by at most `epsilon * total`, with
+    /// probability `1 - delta`.
+    pub fn with_error(epsilon: f64, delta: f64, tracked: usize) -> Self {
+        assert!(epsilon > 0.0 && epsilon < 1.0, "epsilon must be in (0, 1)");
+        assert!(delta > 0.0 && delta < 1.0, "delta must be in (0, 1)");
+
+        let width = (std::f64::consts::E / epsilon).ceil() as usize;
+        let depth = (1.0 / delta).ln().ceil().max(1.0) as usize;
+        CountMinSketch::new(width, depth, tracked)
+    }
+
+    /// Count `element` once.
+    pub fn add(&mut self, element: K) {
+        self.total += 1;
+        let mut estimate = usize::MAX;
+        for cell in self.cells(&element) {
+            self.table[cell] += 1;
+            estimate = estimate.min(self.table[cell]);
+        }
+
+        if let Some(top) = self.top.iter_mut().find(|(k, _)| *k == element) {
+            top.1 = estimate;
+        } else if self.top.len() < self.tracked {
+            self.top.push((element, estimate));
+        } else if let Some(min) = self.top.iter_mut().min_by_key(|(_, n)| *n) {
+            if estimate > min.1 {
+                *min = (element, estimate);
+            }
+        }
+    }
+
+    /// An estimate of the count of `element`, which is never lower than its true count.
+    pub fn get(&self, element: &K) -> usize {
+        self.cells(element)
+            .map(|cell| self.table[cell])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// The number of elements counted.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// The `n` most common of the tracked elements and their estimates, from the most common.
+    pub fn most_common_n(&self, n: usize) -> Vec<(&K, usize)> {
+        let mut top: Vec<_> = self.top.iter().map(|(k, _)| (k, self.get(k))).collect();
+        top.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+        top.truncate(n);
+        top
+    }
+
+    /// The cell of each row that `element` increments, from two hashes of it.
+    fn cells(&self, element: &K) -> impl Iterator<Item = usize> {
+        let mut hasher = SketchHasher::default();
+        element.hash(&mut hasher);
+        let hash = hasher.finish();
+        let step = hash.rotate_left(32) | 1;
+        let width = self.width;
+
+        (0..self.depth).map(move |row| {
+            let column = hash.wrapping_add((row as u64).wrapping_mul(step)) % width as u64;
+            row * width + column as usize
+        })
+    }
+}
+
+/// FNV-1a with a SplitMix64 finalizer, so that the cells of an element, and therefore estimates,
+/// do not change between runs or Rust releases the way `DefaultHasher` may.
+struct SketchHasher(u64);
+
+impl Default for SketchHasher {
+    fn default() -> Self {
+        SketchHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for SketchHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hash = self.0;
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        hash ^ (hash >> 31)
+    }
+}
+
+impl<K: Eq + Hash> Frequencies<K> for CountMinSketch<K> {
+    type Count = usize;
+
+    fn add(&mut self, element: K) {
+        CountMinSketch::add(self, element);
+    }
+
+    fn get(&self, element: &K) -> usize {
+        CountMinSketch::get(self, element)
+    }
+
+    fn most_common_n(&self, n: usize) -> Vec<(&K, usize)> {
+        CountMinSketch::most_common_n(self, n)
+    }
+}
+
+/// An element monitored by a `SpaceSaving` tracker. Its true count is between `count - error` and
+/// `count`.
+#[derive(Clone, Debug)]
+struct Monitored<K> {
+    element: K,
+    count: usize,
+    error: usize,
+}
+
+/// The Space-Saving algorithm: up to `capacity` elements are monitored, and an element that is not
+/// replaces the one with the lowest count, taking over that count as its possible error.
+#[derive(Clone, Debug)]
+pub struct SpaceSaving<K> {
+    capacity: usize,
+    monitored: Vec<Monitored<K>>,
+    index: HashMap<K, usize>,
+    /// The count and index of each monitored element, lowest count first.
+    by_count: BTreeSet<(usize, usize)>,
+    total: usize,
+}
+
+impl<K: Eq + Hash + Clone> SpaceSaving<K> {
+    /// Create a tracker that monitors up to `capacity` elements.
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "a tracker needs to monitor at least one element"
+        );
+
+        SpaceSaving {
+            capacity,
+            monitored: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+            by_count: BTreeSet::new(),
+            total: 0,
+        }
+    }
+
+    /// Count `element` once.
+    pub fn add(&mut self, element: K) {
+        self.total += 1;
+
+        if let Some(&idx) = self.index.get(&element) {
+            let monitored = &mut self.monitored[idx];
+            self.by_count.remove(&(monitored.count, idx));
+            monitored.count += 1;
+            self.by_count.insert((monitored.count, idx));
+        } else if self.monitored.len() < self.capacity {
+            let idx = self.monitored.len();
+            self.index.insert(element.clone(), idx);
+            self.monitored.push(Monitored {
+                element,
+                count: 1,
+                error: 0,
+            });
+            self.by_count.insert((1, idx));
+        } else if let Some((min, idx)) = self.by_count.pop_first() {
+            let replaced = std::mem::replace(
+                &mut self.monitored[idx],
+                Monitored {
+                    element: element.clone(),
+                    count: min + 1,
+                    error: min,
+                },
+            );
+            self.index.remove(&replaced.element);
+            self.index.insert(element, idx);
+            self.by_count.insert((min + 1, idx));
+        }
+    }
+
+    /// An estimate of the count of `element`: an upper bound if it is monitored, and 0 if not.
+    pub fn get(&self, element: &K) -> usize {
+        self.index
+            .get(element)
+            .map_or(0, |&idx| self.monitored[idx].count)
+    }
+
+    /// The lowest and highest possible counts of `element`.
+    pub fn bounds(&self, element: &K) -> (usize, usize) {
+        match self.index.get(element) {
+            Some(&idx) => {
+                let monitored = &self.monitored[idx];
+                (monitored.count - monitored.error, monitored.count)
+            }
+            None if self.monitored.len() < self.capacity => (0, 0),
+            None => (0, self.by_count.first().map_or(0, |&(min, _)| min)),
+        }
+    }
+
+    /// The number of elements counted.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// The `n` monitored elements with the highest counts and their counts, from the most common.
+    pub fn most_common_n(&self, n: usize) -> Vec<(&K, usize)> {
+        self.by_count
+            .iter()
+            .rev()
+            .take(n)
+            .map(|&(count, idx)| (&self.monitored[idx].element, count))
+            .collect()
+    }
+}
+
+impl<K: Eq + Hash + Clone> Frequencies<K> for SpaceSaving<K> {
+    type Count = usize;
+
+    fn add(&mut self, element: K) {
+        SpaceSaving::add(self, element);
+    }
+
+    fn get(&self, element: &K) -> usize {
+        SpaceSaving::get(self, element)
+    }
+
+    fn most_common_n(&self, n: usize) -> Vec<(&K, usize)> {
+        SpaceSaving::most_common_n(self, n)
+    }
+}
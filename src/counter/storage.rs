@@ -0,0 +1,229 @@
+//! The maps in which a counter keeps its counts: a `HashMap` by default, a `BTreeMap` for counters
+//! that iterate in key order, or an `ArrayMap` for keys from a small alphabet.
+
+use super::Entry;
+use std::collections::{btree_map, hash_map, BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// A map from the keys of a counter to their entries.
+pub trait Storage<K, N>: Default {
+    type Iter<'a>: Iterator<Item = (&'a K, &'a Entry<N>)>
+    where
+        Self: 'a,
+        K: 'a,
+        N: 'a;
+    type IntoIter: Iterator<Item = (K, Entry<N>)>;
+
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn get(&self, key: &K) -> Option<&Entry<N>>;
+    fn get_mut(&mut self, key: &K) -> Option<&mut Entry<N>>;
+    /// The entry of `key`, inserting `entry` if there is none.
+    fn get_or_insert(&mut self, key: K, entry: Entry<N>) -> &mut Entry<N>;
+    fn remove(&mut self, key: &K);
+    fn retain<F: FnMut(&K, &mut Entry<N>) -> bool>(&mut self, f: F);
+    fn iter(&self) -> Self::Iter<'_>;
+    fn into_iter(self) -> Self::IntoIter;
+}
+
+impl<K: Eq + Hash, N> Storage<K, N> for HashMap<K, Entry<N>> {
+    type Iter<'a>
+        = hash_map::Iter<'a, K, Entry<N>>
+    where
+        K: 'a,
+        N: 'a;
+    type IntoIter = hash_map::IntoIter<K, Entry<N>>;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, key: &K) -> Option<&Entry<N>> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut Entry<N>> {
+        self.get_mut(key)
+    }
+
+    fn get_or_insert(&mut self, key: K, entry: Entry<N>) -> &mut Entry<N> {
+        self.entry(key).or_insert(entry)
+    }
+
+    fn remove(&mut self, key: &K) {
+        self.remove(key);
+    }
+
+    fn retain<F: FnMut(&K, &mut Entry<N>) -> bool>(&mut self, f: F) {
+        self.retain(f);
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterator::into_iter(self)
+    }
+}
+
+impl<K: Ord, N> Storage<K, N> for BTreeMap<K, Entry<N>> {
+    type Iter<'a>
+        = btree_map::Iter<'a, K, Entry<N>>
+    where
+        K: 'a,
+        N: 'a;
+    type IntoIter = btree_map::IntoIter<K, Entry<N>>;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, key: &K) -> Option<&Entry<N>> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut Entry<N>> {
+        self.get_mut(key)
+    }
+
+    fn get_or_insert(&mut self, key: K, entry: Entry<N>) -> &mut Entry<N> {
+        self.entry(key).or_insert(entry)
+    }
+
+    fn remove(&mut self, key: &K) {
+        self.remove(key);
+    }
+
+    fn retain<F: FnMut(&K, &mut Entry<N>) -> bool>(&mut self, f: F) {
+        self.retain(f);
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.iter()
+    }
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterator::into_iter(self)
+    }
+}
+
+/// A key from an alphabet small enough to index an array of counts.
+pub trait Symbol {
+    /// The number of keys in the alphabet.
+    const SIZE: usize;
+
+    /// The position of the key in the alphabet, which is less than `SIZE` if it is part of it.
+    fn index(&self) -> usize;
+}
+
+impl Symbol for u8 {
+    const SIZE: usize = 256;
+
+    fn index(&self) -> usize {
+        usize::from(*self)
+    }
+}
+
+/// ASCII characters only.
+impl Symbol for char {
+    const SIZE: usize = 128;
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+type Slot<K, N> = Option<(K, Entry<N>)>;
+
+/// A map with a slot for every key of a small alphabet, which iterates in the order of the
+/// alphabet. Inserting a key that is not part of it panics.
+#[derive(Clone, Debug)]
+pub struct ArrayMap<K, N> {
+    slots: Vec<Slot<K, N>>,
+    len: usize,
+}
+
+impl<K, N> Default for ArrayMap<K, N> {
+    fn default() -> Self {
+        ArrayMap {
+            slots: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<K: Symbol, N> Storage<K, N> for ArrayMap<K, N> {
+    type Iter<'a>
+        = std::iter::FilterMap<
+        std::slice::Iter<'a, Slot<K, N>>,
+        fn(&'a Slot<K, N>) -> Option<(&'a K, &'a Entry<N>)>,
+    >
+    where
+        K: 'a,
+        N: 'a;
+    type IntoIter = std::iter::Flatten<std::vec::IntoIter<Slot<K, N>>>;
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, key: &K) -> Option<&Entry<N>> {
+        match self.slots.get(key.index()) {
+            Some(Some((_, entry))) => Some(entry),
+            _ => None,
+        }
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut Entry<N>> {
+        match self.slots.get_mut(key.index()) {
+            Some(Some((_, entry))) => Some(entry),
+            _ => None,
+        }
+    }
+
+    fn get_or_insert(&mut self, key: K, entry: Entry<N>) -> &mut Entry<N> {
+        let index = key.index();
+        assert!(index < K::SIZE, "key {} is not part of the alphabet", index);
+        if self.slots.is_empty() {
+            self.slots.resize_with(K::SIZE, || None);
+        }
+
+        let slot = &mut self.slots[index];
+        if slot.is_none() {
+            self.len += 1;
+        }
+        &mut slot.get_or_insert((key, entry)).1
+    }
+
+    fn remove(&mut self, key: &K) {
+        if let Some(slot) = self.slots.get_mut(key.index()) {
+            if slot.take().is_some() {
+                self.len -= 1;
+            }
+        }
+    }
+
+    fn retain<F: FnMut(&K, &mut Entry<N>) -> bool>(&mut self, mut f: F) {
+        for slot in self.slots.iter_mut() {
+            if let Some((key, entry)) = slot {
+                if !f(key, entry) {
+                    *slot = None;
+                    self.len -= 1;
+                }
+            }
+        }
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.slots
+            .iter()
+            .filter_map(|slot| slot.as_ref().map(|(key, entry)| (key, entry)))
+    }
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.slots.into_iter().flatten()
+    }
+}
@@ -0,0 +1,96 @@
+use advent_of_code_2016::computer::{Computer, Register};
+use std::convert::TryFrom;
+
+type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+
+/// The register with this name.
+fn register(name: &str) -> Register {
+    Register::try_from(name).unwrap()
+}
+
+#[test]
+fn dead_stores() -> Result {
+    let computer = Computer::new("cpy 1 c\ncpy 2 c\nin c\ncpy 3 c\ninc b\ncpy 5 b")?;
+    // Every register is live at the end, but `b` and `c` are overwritten first. Reading input is
+    // not dead, whether the value is used or not.
+    assert_eq!(computer.analyze().dead_stores(), [0, 1, 4]);
+    Ok(())
+}
+
+/// Counts `a` down to zero, with an unreachable `inc c`.
+const COUNTDOWN: &str = "cpy 3 a\ncpy 0 b\ninc b\ndec a\njnz a -2\njnz 1 2\ninc c\nout b";
+
+#[test]
+fn blocks_and_loops() -> Result {
+    let analysis = Computer::new(COUNTDOWN)?.analyze();
+    let blocks: Vec<_> = analysis
+        .blocks()
+        .iter()
+        .map(|b| (b.start..b.end, b.successors.clone(), b.exits))
+        .collect();
+    assert_eq!(
+        blocks,
+        [
+            (0..2, vec![1], false),
+            (2..5, vec![1, 2], false),
+            (5..6, vec![4], false),
+            (6..7, vec![4], false),
+            (7..8, vec![], true),
+        ]
+    );
+    assert_eq!(analysis.block_of(3), 1);
+    assert_eq!(analysis.successors(4), [2, 5]);
+    assert!(analysis.exits(7));
+
+    assert_eq!(analysis.loops().len(), 1);
+    assert_eq!(analysis.loops()[0].header, 1);
+    assert_eq!(analysis.loops()[0].blocks, [1]);
+    assert_eq!(analysis.unreachable(), [6]);
+    assert_eq!(analysis.dead_code(), [6]);
+    Ok(())
+}
+
+#[test]
+fn liveness() -> Result {
+    let analysis = Computer::new(COUNTDOWN)?.analyze();
+    // Every register of the program is live when it halts; `a` and `b` are set before use.
+    assert_eq!(analysis.live_in(0), [register("c")]);
+    assert_eq!(analysis.live_out(0), [register("a"), register("c")]);
+    assert_eq!(
+        analysis.live_in(2),
+        [register("a"), register("b"), register("c")]
+    );
+    Ok(())
+}
+
+#[test]
+fn toggles_and_dynamic_jumps() -> Result {
+    let analysis = Computer::new("tgl 2\njnz 1 2\ncpy 1 a\njnz a b")?.analyze();
+    assert_eq!(analysis.toggle_targets(), [2]);
+    // `cpy 1 a` may become `jnz 1 a`, and `jnz a b` jumps by a register.
+    assert_eq!(analysis.dynamic_jumps(), [2, 3]);
+    assert!(analysis.unreachable().is_empty());
+    assert!(analysis.blocks()[1].dynamic);
+    Ok(())
+}
+
+#[test]
+fn dot() -> Result {
+    let dot = Computer::new(COUNTDOWN)?.analyze().dot().to_string();
+    assert!(dot.starts_with("digraph assembunny {\n"));
+    assert!(dot.ends_with("}\n"));
+    for line in [
+        "    entry -> block0;",
+        "    block1 [label=\"  2  inc b\\l  3  dec a\\l  4  jnz a -2\\l\", style=bold];",
+        "    block1 -> block1;",
+        "    block3 [label=\"  6  inc c (dead)\\l\"];",
+        "    block4 -> exit;",
+    ] {
+        assert!(dot.lines().any(|l| l == line), "missing {}", line);
+    }
+
+    let dot = Computer::new("tgl 1\njnz a b")?.analyze().dot().to_string();
+    assert!(dot.contains("  1  jnz a b (tgl)\\l"));
+    assert!(dot.contains("    block0 -> dynamic0;\n    dynamic0 [shape=circle, label=\"?\"];"));
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use advent_of_code_2016::computer::{Computer, ComputerError, Instruction};
+use std::convert::TryFrom;
+
+type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+
+fn instructions(program: &str) -> Vec<Instruction> {
+    program
+        .lines()
+        .map(|line| Instruction::try_from(line).unwrap())
+        .collect()
+}
+
+fn errors(program: &str) -> Vec<String> {
+    match Computer::new(program) {
+        Err(ComputerError::Parse(errors)) => errors.iter().map(|e| e.to_string()).collect(),
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("{:?} should not parse", program),
+    }
+}
+
+#[test]
+fn labels_and_constants() -> Result {
+    let mut computer = Computer::new(
+        "
+        ; Multiply COUNT by FACTOR, in a.
+        .equ COUNT 4
+                cpy COUNT b
+        outer:
+                cpy FACTOR c
+        inner:  inc a       ; innermost
+                dec c
+                jnz c inner
+                dec b
+                jnz b outer
+                jnz 1 end
+                out a
+        end:
+        .equ FACTOR 3
+        ",
+    )?;
+    assert_eq!(
+        computer.program(),
+        instructions("cpy 4 b\ncpy 3 c\ninc a\ndec c\njnz c -2\ndec b\njnz b -5\njnz 1 2\nout a")
+    );
+    computer.run()?;
+    assert_eq!(computer.registers(), [12, 0, 0, 0]);
+    assert!(computer.output().is_empty());
+    Ok(())
+}
+
+#[test]
+fn plain_input_is_valid() -> Result {
+    let input = include_str!("../inputs/day12.txt");
+    let computer = Computer::new(input)?;
+    assert_eq!(computer.program(), instructions(input.trim_end()));
+    Ok(())
+}
+
+#[test]
+fn symbol_errors() {
+    assert_eq!(
+        errors("top: inc a\ntop: dec a\na: inc b\n1x: inc c"),
+        [
+            "line 2, column 1: label or constant `top` defined twice",
+            "line 3, column 1: invalid label or constant name `a`",
+            "line 4, column 1: invalid label or constant name `1x`",
+        ]
+    );
+    assert_eq!(
+        errors(".equ\n.equ N\n.equ N x1\njnz a nowhere ; comment\n.equ M 1 extra"),
+        [
+            "line 1, column 5: missing argument for `.equ`",
+            "line 2, column 7: missing argument for `.equ`",
+            "line 3, column 8: invalid value `x1`",
+            "line 4, column 7: undefined label or constant `nowhere`",
+            "line 5, column 10: unexpected argument `extra`",
+        ]
+    );
+}
@@ -0,0 +1,150 @@
+//! The transpiled programs are compiled with `rustc` and `cc`, and run against the interpreter.
+//! Without a compiler on the path, the corresponding tests only check the generated source.
+
+use advent_of_code_2016::computer::codegen::Language;
+use advent_of_code_2016::computer::{Computer, OverflowPolicy, Width};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+
+/// Compile the program as transpiled into `language`, and run it with `stdin` as its input.
+/// Returns its standard output and whether it succeeded, or `None` without a compiler.
+fn native(
+    computer: &Computer,
+    language: Language,
+    name: &str,
+    stdin: &str,
+) -> Option<(String, bool)> {
+    let dir = std::env::temp_dir();
+    let stem = format!("asmb-{}-{}-{:?}", std::process::id(), name, language);
+    let binary = dir.join(&stem);
+    let (source, mut compiler) = match language {
+        Language::Rust => {
+            let mut rustc = Command::new("rustc");
+            rustc.args(["-O", "--edition", "2018"]);
+            (dir.join(format!("{}.rs", stem)), rustc)
+        }
+        Language::C => {
+            let mut cc = Command::new("cc");
+            cc.arg("-O2");
+            (dir.join(format!("{}.c", stem)), cc)
+        }
+    };
+    std::fs::write(&source, computer.transpile(language).to_string()).unwrap();
+
+    let compiled = compiler.arg("-o").arg(&binary).arg(&source).output().ok()?;
+    assert!(
+        compiled.status.success(),
+        "{}",
+        String::from_utf8_lossy(&compiled.stderr)
+    );
+
+    let mut child = Command::new(&binary)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    let _ = std::fs::remove_file(&source);
+    let _ = std::fs::remove_file(&binary);
+    Some((
+        String::from_utf8(output.stdout).unwrap(),
+        output.status.success(),
+    ))
+}
+
+/// What a transpiled program prints: the values it transmits, then the registers if it halts.
+fn expected(computer: &Computer, halted: bool) -> String {
+    let mut expected: String = computer
+        .output()
+        .iter()
+        .map(|v| format!("out {}\n", v))
+        .collect();
+    if halted {
+        for (register, value) in computer.named_registers() {
+            expected.push_str(&format!("{} = {}\n", register, value));
+        }
+    }
+    expected
+}
+
+/// Check that `computer` runs the same natively as interpreted, from its current state.
+fn check(computer: &Computer, name: &str, stdin: &str) -> Result {
+    let mut interpreted = computer.clone();
+    for line in stdin.lines() {
+        interpreted.input(line.parse()?);
+    }
+    let halted = interpreted.optimize().run().is_ok();
+
+    for language in [Language::Rust, Language::C] {
+        if let Some((output, success)) = native(computer, language, name, stdin) {
+            assert_eq!(success, halted, "{:?}", language);
+            assert_eq!(output, expected(&interpreted, halted), "{:?}", language);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn day12() -> Result {
+    let mut computer = Computer::new(include_str!("../inputs/day12.txt"))?;
+    check(&computer, "day12-1", "")?;
+    computer.set_registers([0, 0, 1, 0]);
+    check(&computer, "day12-2", "")
+}
+
+#[test]
+fn day23() -> Result {
+    let mut computer = Computer::new(include_str!("../inputs/day23.txt"))?;
+    computer.set_registers([7, 0, 0, 0]);
+    check(&computer, "day23", "")
+}
+
+#[test]
+fn input_and_output() -> Result {
+    let computer = Computer::new("in a\nin b\nout b\nout a\nin 1\ninc c")?;
+    check(&computer, "io", "3\n-4\n5\n")?;
+    // Running out of input is an error.
+    check(&computer, "io-short", "3\n-4\n")
+}
+
+#[test]
+fn overflow() -> Result {
+    let mut computer = Computer::new("cpy b a\ninc a\nout a\ndec c\ndec c")?;
+    computer.set_registers([0, i128::from(i64::MAX), i128::from(i64::MIN), 0]);
+    for (width, policy) in [
+        (Width::I64, OverflowPolicy::Wrap),
+        (Width::I64, OverflowPolicy::Saturate),
+        (Width::I64, OverflowPolicy::Error),
+        (Width::I128, OverflowPolicy::Error),
+    ] {
+        computer.set_width(width).set_overflow_policy(policy);
+        check(&computer, &format!("overflow-{:?}-{:?}", width, policy), "")?;
+    }
+    Ok(())
+}
+
+#[test]
+fn source() -> Result {
+    let computer = Computer::new("cpy 2 a\ntgl a\ninc b\ndec b")?;
+    let rust = computer.transpile(Language::Rust).to_string();
+    assert!(rust.starts_with("// Generated from an assembunny program.\n"));
+    assert!(rust.contains("const NEXT: [[usize; 3]; 4] = ["));
+    assert!(rust.contains("            (3, 1) => {\n                // inc b\n"));
+
+    let c = Computer::new("inc a\nout a")?
+        .transpile(Language::C)
+        .to_string();
+    assert!(c.contains("int main(int argc, char **argv)"));
+    assert!(!c.contains("NEXT"));
+    Ok(())
+}
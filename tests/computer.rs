@@ -0,0 +1,591 @@
+use advent_of_code_2016::computer::{
+    Argument, Breakpoint, Computer, ComputerError, Instruction, OverflowPolicy, Register, Snapshot,
+    Step, Value, Width,
+};
+use std::convert::TryFrom;
+
+type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+
+/// The register with this name.
+fn register(name: &str) -> Register {
+    Register::try_from(name).unwrap()
+}
+
+/// Run a program to completion from the given registers, returning the final registers.
+fn run(program: &str, registers: [i128; 4]) -> std::result::Result<Vec<i128>, ComputerError> {
+    Ok(Computer::new(program)?
+        .set_registers(registers)
+        .run_with_limit(10_000)?
+        .registers())
+}
+
+fn instruction(s: &str) -> Instruction {
+    Instruction::try_from(s).unwrap()
+}
+
+#[test]
+fn copy() -> Result {
+    assert_eq!(run("cpy 41 a", [0; 4])?, [41, 0, 0, 0]);
+    assert_eq!(run("cpy -7 d", [0; 4])?, [0, 0, 0, -7]);
+    assert_eq!(run("cpy c b", [0, 0, 5, 0])?, [0, 5, 5, 0]);
+    // Copying to a value is invalid, and skipped.
+    assert_eq!(run("cpy a 1\ninc b", [3, 0, 0, 0])?, [3, 1, 0, 0]);
+    Ok(())
+}
+
+#[test]
+fn copy_out_of_range_value() {
+    assert!(matches!(
+        run("cpy 2147483648 a", [0; 4]),
+        Err(ComputerError::Overflow(0))
+    ));
+}
+
+#[test]
+fn increment_and_decrement() -> Result {
+    assert_eq!(run("inc a\ninc a\ndec b", [0, 0, 0, 0])?, [2, -1, 0, 0]);
+    // With a value instead of a register, they do nothing.
+    assert_eq!(run("inc 1\ndec 2\ninc c", [0; 4])?, [0, 0, 1, 0]);
+    Ok(())
+}
+
+#[test]
+fn overflow_policies() -> Result {
+    let max = i128::from(i32::MAX);
+    let mut computer = Computer::new("inc a")?;
+
+    computer.set_registers([max, 0, 0, 0]);
+    assert!(matches!(computer.run(), Err(ComputerError::Overflow(0))));
+
+    computer.reset().set_overflow_policy(OverflowPolicy::Wrap);
+    computer.set_registers([max, 0, 0, 0]).run()?;
+    assert_eq!(computer.registers()[0], i128::from(i32::MIN));
+
+    computer
+        .reset()
+        .set_overflow_policy(OverflowPolicy::Saturate);
+    computer.set_registers([max, 0, 0, 0]).run()?;
+    assert_eq!(computer.registers()[0], max);
+
+    computer.reset().set_width(Width::I64);
+    computer.set_registers([max, 0, 0, 0]).run()?;
+    assert_eq!(computer.registers()[0], max + 1);
+    Ok(())
+}
+
+#[test]
+fn optimized_loops_overflow_like_plain_ones() -> Result {
+    // a += b * d
+    let program = "cpy b c\ninc a\ndec c\njnz c -2\ndec d\njnz d -5";
+    let max = i128::from(i32::MAX);
+    let policies = [
+        OverflowPolicy::Wrap,
+        OverflowPolicy::Saturate,
+        OverflowPolicy::Error,
+    ];
+
+    for policy in policies {
+        let mut plain = Computer::new(program)?;
+        plain
+            .set_overflow_policy(policy)
+            .set_registers([max - 60, 7, 0, 9]);
+        let expected = plain.run().map(|c| c.registers());
+
+        for compiled in [false, true] {
+            let mut optimized = Computer::new(program)?;
+            optimized
+                .set_overflow_policy(policy)
+                .set_registers([max - 60, 7, 0, 9])
+                .optimize();
+            let actual = if compiled {
+                optimized.run_compiled()
+            } else {
+                optimized.run()
+            };
+            let actual = actual.map(|c| c.registers());
+            assert_eq!(format!("{:?}", actual), format!("{:?}", expected));
+        }
+    }
+
+    // The product alone does not fit, but the result does.
+    for policy in policies {
+        for compiled in [false, true] {
+            let mut computer = Computer::new(program)?;
+            computer
+                .set_overflow_policy(policy)
+                .set_registers([-10, 65536, 0, 32768])
+                .optimize();
+            if compiled {
+                computer.run_compiled()?;
+            } else {
+                computer.run()?;
+            }
+            assert_eq!(computer.registers(), [2147483638, 65536, 0, 0]);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn jump_if_not_zero() -> Result {
+    // Taken: skips the `inc b`.
+    assert_eq!(run("jnz a 2\ninc b\ninc c", [1, 0, 0, 0])?, [1, 0, 1, 0]);
+    // Not taken.
+    assert_eq!(run("jnz a 2\ninc b\ninc c", [0, 0, 0, 0])?, [0, 1, 1, 0]);
+    // A constant condition, and an offset from a register.
+    assert_eq!(run("jnz 1 d\ninc b\ninc c", [0, 0, 0, 2])?, [0, 0, 1, 2]);
+    assert_eq!(run("jnz 0 5\ninc b", [0; 4])?, [0, 1, 0, 0]);
+    // Jumping past the end halts.
+    assert_eq!(run("jnz 1 10\ninc b", [0; 4])?, [0, 0, 0, 0]);
+    Ok(())
+}
+
+#[test]
+fn jump_before_the_start() {
+    assert!(matches!(
+        run("inc a\njnz 1 -2", [0; 4]),
+        Err(ComputerError::InvalidInstructionPointer(-1))
+    ));
+}
+
+#[test]
+fn jump_by_extreme_offsets() {
+    for (offset, target) in [
+        (i128::MAX, i128::MAX),
+        (i128::MIN, i128::MIN + 1),
+        (i128::from(i32::MAX), i128::from(i32::MAX) + 1),
+    ] {
+        let mut computer = Computer::new("inc a\njnz 1 b").unwrap();
+        computer
+            .set_width(Width::I128)
+            .set_registers([0, offset, 0, 0]);
+        match computer.run() {
+            Err(ComputerError::InvalidInstructionPointer(t)) => assert_eq!(t, target),
+            r => panic!("offset {}: {:?}", offset, r.map(|c| c.registers())),
+        }
+    }
+}
+
+#[test]
+fn toggle_variants() -> Result {
+    let cases = [
+        ("cpy a b", "jnz a b"),
+        ("jnz a b", "cpy a b"),
+        ("inc a", "dec a"),
+        ("dec a", "inc a"),
+        ("tgl a", "inc a"),
+        ("out a", "inc a"),
+        ("in a", "inc a"),
+    ];
+
+    for &(before, after) in cases.iter() {
+        let mut computer = Computer::new(&format!("tgl 1\n{}", before))?;
+        computer.step()?;
+        assert_eq!(computer.program()[1], instruction(after), "{}", before);
+    }
+    Ok(())
+}
+
+#[test]
+fn toggle_itself() -> Result {
+    let mut computer = Computer::new("tgl 0")?;
+    assert_eq!(
+        computer.step()?,
+        Step::Executed {
+            instruction_pointer: 0,
+            instruction: instruction("tgl 0"),
+        }
+    );
+    assert_eq!(computer.program(), [instruction("inc 0")]);
+    assert!(computer.is_halted());
+    Ok(())
+}
+
+#[test]
+fn toggle_out_of_range() -> Result {
+    for offset in [-1, 2, 100, i128::from(i32::MAX), i128::MIN, i128::MAX] {
+        let mut computer = Computer::new("tgl a\ninc b")?;
+        computer
+            .set_width(Width::I128)
+            .set_registers([offset, 0, 0, 0]);
+        computer.run()?;
+        assert_eq!(computer.program(), Computer::new("tgl a\ninc b")?.program());
+        assert_eq!(computer.registers(), [offset, 1, 0, 0]);
+    }
+    Ok(())
+}
+
+#[test]
+fn toggle_into_invalid_instruction() -> Result {
+    // `jnz 1 a` becomes `cpy 1 a`, which is valid, then `cpy 2 3` which is not and gets skipped.
+    let program = "tgl 1\njnz 1 a\ntgl 1\njnz 2 3\ninc b";
+    assert_eq!(run(program, [0; 4])?, [1, 1, 0, 0]);
+    Ok(())
+}
+
+#[test]
+fn toggle_example() -> Result {
+    let program = "cpy 2 a\ntgl a\ntgl a\ntgl a\ncpy 1 a\ndec a\ndec a";
+    assert_eq!(run(program, [0; 4])?, [3, 0, 0, 0]);
+    Ok(())
+}
+
+#[test]
+fn copy_example() -> Result {
+    let program = "cpy 41 a\ninc a\ninc a\ndec a\njnz a 2\ndec a";
+    assert_eq!(run(program, [0; 4])?, [42, 0, 0, 0]);
+    Ok(())
+}
+
+#[test]
+fn out() -> Result {
+    let mut computer = Computer::new("out 0\nout a\ninc a\nout a")?;
+    computer.set_registers([5, 0, 0, 0]).run()?;
+    assert_eq!(computer.output(), [0, 5, 6]);
+    Ok(())
+}
+
+#[test]
+fn input() -> Result {
+    let mut computer = Computer::new("in a\nin 3\nin b")?;
+    computer.input(4).input(5).input(6).run()?;
+    assert_eq!(computer.registers(), [4, 6, 0, 0]);
+
+    computer.reset().input(1);
+    assert!(matches!(computer.run(), Err(ComputerError::NoInput(1))));
+    Ok(())
+}
+
+#[test]
+fn step_limit() -> Result {
+    let mut computer = Computer::new("jnz 1 0")?;
+    assert!(matches!(
+        computer.run_with_limit(100),
+        Err(ComputerError::StepLimitExceeded(100))
+    ));
+    Ok(())
+}
+
+#[test]
+fn parse_errors() {
+    for (program, expected) in [
+        ("foo a", "line 1, column 1: invalid opcode `foo`"),
+        ("inc", "line 1, column 4: missing argument for `inc`"),
+        ("cpy 1 e", "line 1, column 7: invalid register `e`"),
+        (
+            "cpy x1 a",
+            "line 1, column 5: undefined label or constant `x1`",
+        ),
+        (
+            "cpy 1 a junk",
+            "line 1, column 9: unexpected argument `junk`",
+        ),
+        (
+            "inc a b c",
+            "line 1, column 7: unexpected argument `b`\nline 1, column 9: unexpected argument `c`",
+        ),
+    ] {
+        match Computer::new(program) {
+            Err(e) => assert_eq!(e.to_string(), expected),
+            Ok(_) => panic!("{} should not parse", program),
+        }
+    }
+}
+
+#[test]
+fn instruction_from_str() {
+    assert_eq!(
+        instruction("cpy -3 c"),
+        Instruction::Copy(
+            Argument::Value(Value(-3)),
+            Argument::Register(register("c"))
+        )
+    );
+    assert!(Instruction::try_from("").is_err());
+    assert!(Instruction::try_from("jnz 1").is_err());
+}
+
+#[test]
+fn restore_is_all_or_nothing() -> Result {
+    let mut computer = Computer::new("cpy 5 a\ninc b")?;
+    computer.run()?;
+    let snapshot = computer.snapshot();
+
+    let mut undeclared = snapshot.clone();
+    undeclared.registers.insert(0, (register("a"), 99));
+    undeclared.registers.push((register("e"), 1));
+    assert!(matches!(
+        computer.restore(&undeclared),
+        Err(ComputerError::InvalidRegister(_))
+    ));
+    assert_eq!(computer.registers(), [5, 1, 0, 0]);
+
+    let mut too_wide = snapshot.clone();
+    too_wide.registers = vec![(register("a"), 99), (register("b"), i128::MAX)];
+    assert!(matches!(
+        computer.restore(&too_wide),
+        Err(ComputerError::InvalidValue(_))
+    ));
+    assert_eq!(computer.registers(), [5, 1, 0, 0]);
+
+    computer.reset().restore(&snapshot)?;
+    assert_eq!(computer.registers(), [5, 1, 0, 0]);
+    assert_eq!(computer.snapshot(), snapshot);
+    Ok(())
+}
+
+#[test]
+fn snapshot_round_trip() -> Result {
+    let mut computer = Computer::new("inc a\nout a\ninc a")?;
+    computer
+        .set_width(Width::I64)
+        .set_overflow_policy(OverflowPolicy::Wrap)
+        .set_registers([i128::from(i64::MAX) - 1, 0, 0, 0]);
+    computer.step()?;
+    computer.step()?;
+    let snapshot = computer.snapshot();
+    let text = snapshot.to_string();
+    assert!(text.starts_with(&format!(
+        "registers a={} b=0 c=0 d=0\nwidth i64\noverflow wrap\nip 2\noutput {}\nprogram\n",
+        i64::MAX,
+        i64::MAX
+    )));
+    let parsed: Snapshot = text.parse()?;
+    assert_eq!(parsed, snapshot);
+
+    // Restoring brings back the settings the snapshot was taken with.
+    let mut restored = Computer::new("")?;
+    restored.restore(&parsed)?.run()?;
+    assert_eq!(restored.registers()[0], i128::from(i64::MIN));
+
+    assert!(matches!(
+        text.replace("width i64", "width i16").parse::<Snapshot>(),
+        Err(ComputerError::InvalidSnapshot(line)) if line == "width i16"
+    ));
+    Ok(())
+}
+
+#[test]
+fn step_by_step() -> Result {
+    let mut computer = Computer::new("cpy 2 a\ntgl a\ninc b\ndec b")?;
+    assert_eq!(
+        computer.step()?,
+        Step::Executed {
+            instruction_pointer: 0,
+            instruction: instruction("cpy 2 a"),
+        }
+    );
+    assert_eq!(computer.registers(), [2, 0, 0, 0]);
+    assert_eq!(computer.instruction_pointer(), 1);
+
+    computer.step()?;
+    assert_eq!(computer.program()[3], instruction("inc b"));
+    computer.step()?;
+    computer.step()?;
+    assert_eq!(computer.registers(), [2, 2, 0, 0]);
+    assert!(computer.is_halted());
+    assert_eq!(computer.step()?, Step::Halted);
+    Ok(())
+}
+
+#[test]
+fn run_until() -> Result {
+    let mut computer = Computer::new("inc a\njnz 1 -1")?;
+    computer.run_until(|computer| computer.registers()[0] == 10)?;
+    assert_eq!(computer.registers()[0], 10);
+    assert_eq!(computer.instruction_pointer(), 1);
+
+    // A program that halts first stops there.
+    let mut computer = Computer::new("inc a")?;
+    computer.run_until(|_| false)?;
+    assert!(computer.is_halted());
+    Ok(())
+}
+
+#[test]
+fn breakpoints() -> Result {
+    let mut computer = Computer::new("cpy 3 a\ninc b\ndec a\njnz a -2\ncpy 7 c")?;
+    computer
+        .add_breakpoint(Breakpoint::InstructionPointer(3))
+        .add_breakpoint(Breakpoint::InstructionPointer(3))
+        .add_breakpoint(Breakpoint::RegisterEquals(register("b"), 2))
+        .add_breakpoint(Breakpoint::RegisterChanged(register("c")));
+    assert_eq!(computer.breakpoints().len(), 3);
+
+    let mut hits = Vec::new();
+    while let Some(breakpoint) = computer.run_to_breakpoint()? {
+        hits.push((breakpoint, computer.registers()));
+    }
+    assert_eq!(
+        hits,
+        [
+            (Breakpoint::InstructionPointer(3), vec![2, 1, 0, 0]),
+            (
+                Breakpoint::RegisterEquals(register("b"), 2),
+                vec![2, 2, 0, 0]
+            ),
+            (Breakpoint::InstructionPointer(3), vec![1, 2, 0, 0]),
+            (Breakpoint::InstructionPointer(3), vec![0, 3, 0, 0]),
+            (Breakpoint::RegisterChanged(register("c")), vec![0, 3, 7, 0]),
+        ]
+    );
+    assert!(computer.is_halted());
+
+    computer
+        .reset()
+        .remove_breakpoint(Breakpoint::InstructionPointer(3));
+    assert_eq!(
+        computer.run_to_breakpoint()?,
+        Some(Breakpoint::RegisterEquals(register("b"), 2))
+    );
+    computer.clear_breakpoints();
+    assert_eq!(computer.run_to_breakpoint()?, None);
+    assert_eq!(computer.registers(), [0, 3, 7, 0]);
+    Ok(())
+}
+
+#[test]
+fn reset_undoes_everything_but_settings() -> Result {
+    let mut computer = Computer::new("tgl 1\ndec a\nout a\nin b")?;
+    computer
+        .add_breakpoint(Breakpoint::InstructionPointer(2))
+        .input(4);
+    computer.run()?;
+    assert_eq!(computer.program()[1], instruction("inc a"));
+    assert_eq!(computer.registers(), [1, 4, 0, 0]);
+
+    computer.reset();
+    assert_eq!(computer.program()[1], instruction("dec a"));
+    assert_eq!(computer.registers(), [0; 4]);
+    assert_eq!(computer.instruction_pointer(), 0);
+    assert!(computer.output().is_empty());
+    assert!(matches!(computer.run(), Err(ComputerError::NoInput(3))));
+    assert_eq!(computer.breakpoints(), [Breakpoint::InstructionPointer(2)]);
+    Ok(())
+}
+
+#[test]
+fn register_names() -> Result {
+    for (index, name) in [(0, "a"), (3, "d"), (25, "z")] {
+        let register = Register::new(index)?;
+        assert_eq!(register, Register::try_from(name)?);
+        assert_eq!(register.index(), index);
+        assert_eq!(register.to_string(), name);
+    }
+    assert!(matches!(
+        Register::new(26),
+        Err(ComputerError::InvalidRegister(r)) if r == "26"
+    ));
+    assert!(Register::try_from("A").is_err());
+    Ok(())
+}
+
+#[test]
+fn custom_registers() -> Result {
+    let mut computer = Computer::with_registers("cpy x z\ninc z", "zyx")?;
+    assert_eq!(
+        computer.register_names(),
+        [register("z"), register("y"), register("x")]
+    );
+    computer.set_registers([1, 2, 3]).run()?;
+    assert_eq!(computer.registers(), [4, 2, 3]);
+    assert_eq!(computer.register(register("z")), 4);
+
+    computer.reset().set_register(register("x"), 9)?.run()?;
+    assert_eq!(computer.registers(), [10, 0, 9]);
+    assert!(matches!(
+        computer.set_register(register("a"), 1),
+        Err(ComputerError::InvalidRegister(r)) if r == "a"
+    ));
+    assert_eq!(computer.registers(), [10, 0, 9]);
+
+    assert!(Computer::with_registers("inc a", "xyz").is_err());
+    assert!(Computer::with_registers("", "aB").is_err());
+    Ok(())
+}
+
+#[test]
+fn infinite_loops() -> Result {
+    let mut computer = Computer::new("out 5\ncpy 1 a\nout a\nout 0\njnz a -2")?;
+    computer.detect_loops();
+    match computer.run() {
+        Err(ComputerError::InfiniteLoop {
+            instruction_pointer,
+            output_start,
+        }) => {
+            assert_eq!(instruction_pointer, 2);
+            assert_eq!(output_start, 1);
+            assert_eq!(computer.output(), [5, 1, 0]);
+        }
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+
+    // Toggling an instruction changes the state, even at the same address with the same registers.
+    let mut computer = Computer::new("jnz 1 2\njnz 1 3\ntgl -2\njnz 1 -3")?;
+    computer.detect_loops().run()?;
+    assert_eq!(computer.program()[0], instruction("cpy 1 2"));
+
+    // Optimized loops are left before their state could repeat.
+    let mut computer = Computer::new("cpy 1000 b\ninc a\ndec b\njnz b -2")?;
+    computer.detect_loops().optimize().run()?;
+    assert_eq!(computer.registers(), [1000, 0, 0, 0]);
+    Ok(())
+}
+
+#[test]
+fn trace_ring_buffer() -> Result {
+    let mut computer = Computer::new("cpy 3 a\ndec a\njnz a -1")?;
+    computer.record_trace(3).run()?;
+    let trace: Vec<_> = computer.trace().cloned().collect();
+    assert_eq!(trace.len(), 3);
+    assert_eq!(
+        trace[0].step,
+        Step::Executed {
+            instruction_pointer: 2,
+            instruction: instruction("jnz a -1"),
+        }
+    );
+    assert_eq!(
+        (&trace[1].registers_before, &trace[1].registers_after),
+        (&vec![1, 0, 0, 0], &vec![0, 0, 0, 0])
+    );
+    assert_eq!(
+        trace[2].to_string(),
+        "   2  jnz a -1          [0, 0, 0, 0] -> [0, 0, 0, 0]"
+    );
+
+    computer.record_trace(1);
+    assert_eq!(computer.trace().count(), 1);
+    computer.reset();
+    assert_eq!(computer.trace().count(), 0);
+    computer.record_trace(0).run()?;
+    assert_eq!(computer.trace().count(), 0);
+
+    let mut written = Vec::new();
+    computer.reset().run_traced(&mut written)?;
+    let written = String::from_utf8(written)?;
+    assert_eq!(written.lines().count(), 7);
+    assert_eq!(
+        written.lines().next(),
+        Some("   0  cpy 3 a           [0, 0, 0, 0] -> [3, 0, 0, 0]")
+    );
+    Ok(())
+}
+
+#[test]
+fn hot_spots() -> Result {
+    let mut computer = Computer::new("cpy 3 a\ndec a\njnz a -1\ninc b")?;
+    assert!(computer.hot_spots(2).is_none());
+    computer.profile().run()?;
+    assert_eq!(computer.execution_counts(), Some(&[1, 3, 3, 1][..]));
+    assert_eq!(
+        computer.hot_spots(2).unwrap().to_string(),
+        "           3  37.50%     1  dec a\n           3  37.50%     2  jnz a -1\n"
+    );
+
+    // An optimized loop counts once, at its first address.
+    let mut computer = Computer::new("cpy 3 a\ninc b\ndec a\njnz a -2")?;
+    computer.optimize().profile().run()?;
+    assert_eq!(computer.execution_counts(), Some(&[1, 1, 0, 0][..]));
+    Ok(())
+}
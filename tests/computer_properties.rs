@@ -0,0 +1,320 @@
+//! Property tests on generated programs, and a differential test against a reference interpreter
+//! written as plainly as possible.
+
+use advent_of_code_2016::computer::{
+    Argument, Computer, ComputerError, Instruction, OverflowPolicy, Register, Value, Width,
+};
+use proptest::prelude::*;
+use std::convert::TryFrom;
+
+const MAX_STEPS: usize = 1_000;
+
+fn argument() -> impl Strategy<Value = Argument> {
+    prop_oneof![
+        4 => (0..4usize).prop_map(|r| Argument::Register(Register::new(r).unwrap())),
+        4 => (-4..5i128).prop_map(|v| Argument::Value(Value(v))),
+        1 => prop_oneof![
+            Just(i128::MIN),
+            Just(i128::MAX),
+            Just(i128::from(i32::MIN)),
+            Just(i128::from(i32::MAX)),
+        ]
+        .prop_map(|v| Argument::Value(Value(v))),
+    ]
+}
+
+fn instruction() -> impl Strategy<Value = Instruction> {
+    prop_oneof![
+        (argument(), argument()).prop_map(|(a, b)| Instruction::Copy(a, b)),
+        argument().prop_map(Instruction::Increment),
+        argument().prop_map(Instruction::Decrement),
+        (argument(), argument()).prop_map(|(a, b)| Instruction::JumpIfNotZero(a, b)),
+        argument().prop_map(Instruction::Toggle),
+        argument().prop_map(Instruction::Out),
+    ]
+}
+
+fn register() -> impl Strategy<Value = Argument> {
+    (0..4usize).prop_map(|r| Argument::Register(Register::new(r).unwrap()))
+}
+
+/// Either a single instruction, or one of the loops that `Computer::optimize` recognizes.
+fn chunk() -> impl Strategy<Value = Vec<Instruction>> {
+    prop_oneof![
+        8 => instruction().prop_map(|i| vec![i]),
+        1 => (register(), register()).prop_map(|(a, b)| vec![
+            Instruction::Increment(a),
+            Instruction::Decrement(b),
+            Instruction::JumpIfNotZero(b, Argument::Value(Value(-2))),
+        ]),
+        1 => (argument(), register(), register(), register()).prop_map(|(x, a, c, d)| vec![
+            Instruction::Copy(x, c),
+            Instruction::Increment(a),
+            Instruction::Decrement(c),
+            Instruction::JumpIfNotZero(c, Argument::Value(Value(-2))),
+            Instruction::Decrement(d),
+            Instruction::JumpIfNotZero(d, Argument::Value(Value(-5))),
+        ]),
+    ]
+}
+
+fn program() -> impl Strategy<Value = Vec<Instruction>> {
+    prop::collection::vec(chunk(), 1..8).prop_map(|chunks| chunks.concat())
+}
+
+fn source(program: &[Instruction]) -> String {
+    program.iter().map(|i| format!("{}\n", i)).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Halted,
+    InvalidInstructionPointer,
+    StepLimitExceeded,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct State {
+    outcome: Outcome,
+    registers: Vec<i128>,
+    output: Vec<i128>,
+}
+
+/// Reference semantics, with `i128` registers that wrap around.
+fn reference(mut program: Vec<Instruction>, mut registers: [i128; 4], max_steps: usize) -> State {
+    fn get(registers: &[i128; 4], a: Argument) -> i128 {
+        match a {
+            Argument::Value(Value(v)) => v,
+            Argument::Register(r) => registers[r.index()],
+        }
+    }
+
+    let mut output = Vec::new();
+    let mut ip: i128 = 0;
+    let mut outcome = Outcome::StepLimitExceeded;
+    for step in 0..=max_steps {
+        let i = match usize::try_from(ip).ok().and_then(|ip| program.get(ip)) {
+            Some(&i) => i,
+            None => {
+                outcome = Outcome::Halted;
+                break;
+            }
+        };
+        if step == max_steps {
+            break;
+        }
+
+        let mut next = ip + 1;
+        match i {
+            Instruction::Copy(a, Argument::Register(r)) => {
+                registers[r.index()] = get(&registers, a);
+            }
+            Instruction::Increment(Argument::Register(r)) => {
+                registers[r.index()] = registers[r.index()].wrapping_add(1);
+            }
+            Instruction::Decrement(Argument::Register(r)) => {
+                registers[r.index()] = registers[r.index()].wrapping_sub(1);
+            }
+            Instruction::JumpIfNotZero(a, b) if get(&registers, a) != 0 => {
+                next = ip.saturating_add(get(&registers, b));
+                if next < 0 || next > i128::from(i32::MAX) {
+                    outcome = Outcome::InvalidInstructionPointer;
+                    break;
+                }
+            }
+            Instruction::Toggle(a) => {
+                let target = ip.saturating_add(get(&registers, a));
+                if let Some(t) = usize::try_from(target).ok().filter(|&t| t < program.len()) {
+                    program[t] = match program[t] {
+                        Instruction::Copy(a, b) => Instruction::JumpIfNotZero(a, b),
+                        Instruction::JumpIfNotZero(a, b) => Instruction::Copy(a, b),
+                        Instruction::Increment(a) => Instruction::Decrement(a),
+                        Instruction::Decrement(a)
+                        | Instruction::Toggle(a)
+                        | Instruction::Out(a)
+                        | Instruction::In(a) => Instruction::Increment(a),
+                    };
+                }
+            }
+            Instruction::Out(a) => output.push(get(&registers, a)),
+            _ => (),
+        }
+        ip = next;
+    }
+
+    State {
+        outcome,
+        registers: registers.to_vec(),
+        output,
+    }
+}
+
+/// Run a computer the way `reference` runs a program.
+fn actual(computer: &mut Computer, compiled: bool, max_steps: usize) -> State {
+    let result = if compiled {
+        computer.run_compiled().map(|_| ())
+    } else {
+        computer.run_with_limit(max_steps).map(|_| ())
+    };
+    let outcome = match result {
+        Ok(()) => Outcome::Halted,
+        Err(ComputerError::InvalidInstructionPointer(_)) => Outcome::InvalidInstructionPointer,
+        Err(ComputerError::StepLimitExceeded(_)) => Outcome::StepLimitExceeded,
+        Err(e) => panic!("unexpected error: {}", e),
+    };
+
+    State {
+        outcome,
+        registers: computer.registers(),
+        output: computer.output().to_vec(),
+    }
+}
+
+fn computer(program: &[Instruction], registers: [i128; 4]) -> Computer {
+    let mut computer = Computer::new(&source(program)).unwrap();
+    computer
+        .set_width(Width::I128)
+        .set_overflow_policy(OverflowPolicy::Wrap)
+        .set_registers(registers);
+    computer
+}
+
+fn width() -> impl Strategy<Value = Width> {
+    prop_oneof![Just(Width::I32), Just(Width::I64), Just(Width::I128)]
+}
+
+fn overflow_policy() -> impl Strategy<Value = OverflowPolicy> {
+    prop_oneof![
+        Just(OverflowPolicy::Wrap),
+        Just(OverflowPolicy::Saturate),
+        Just(OverflowPolicy::Error),
+    ]
+}
+
+/// A register value, possibly one that does not fit in the computer's width.
+fn register_value() -> impl Strategy<Value = i128> {
+    prop_oneof![
+        4 => -3..8i128,
+        1 => prop_oneof![
+            Just(i128::MIN),
+            Just(i128::MAX),
+            Just(i128::from(i32::MIN)),
+            Just(i128::from(i32::MAX)),
+            Just(i128::from(i32::MIN) - 1),
+            Just(i128::from(i32::MAX) + 1),
+            Just(i128::from(i64::MIN)),
+            Just(i128::from(i64::MAX)),
+        ],
+    ]
+}
+
+proptest! {
+    #[test]
+    fn parse_never_panics(text in "((([a-z]{0,4}|-?[0-9]{1,40}|[ :;.]) ?){0,8}\n){0,6}") {
+        let _ = Computer::new(&text);
+    }
+
+    #[test]
+    fn listing_parses_back(program in program()) {
+        let computer = Computer::new(&source(&program)).unwrap();
+        prop_assert_eq!(computer.program(), &program[..]);
+        let listed = Computer::new(&computer.listing().annotated().to_string()).unwrap();
+        prop_assert_eq!(listed.program(), &program[..]);
+    }
+
+    #[test]
+    fn programs_halt_or_fail_cleanly(program in program(), a in -3..8i128) {
+        let mut computer = Computer::new(&source(&program)).unwrap();
+        computer.set_registers([a, 0, 0, 0]);
+        match computer.run_with_limit(MAX_STEPS) {
+            Ok(computer) => prop_assert!(computer.is_halted()),
+            Err(ComputerError::InvalidInstructionPointer(target)) => {
+                prop_assert!(target < 0 || target > i128::from(i32::MAX));
+            }
+            Err(ComputerError::StepLimitExceeded(MAX_STEPS)) => prop_assert!(!computer.is_halted()),
+            // Extreme literals do not fit in 32 bits.
+            Err(ComputerError::Overflow(_)) => (),
+            Err(e) => prop_assert!(false, "unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn interpreter_matches_reference(program in program(), a in -3..8i128, b in -3..8i128) {
+        let expected = reference(program.clone(), [a, b, 0, 0], MAX_STEPS);
+        let actual = actual(&mut computer(&program, [a, b, 0, 0]), false, MAX_STEPS);
+        prop_assert_eq!(actual, expected);
+    }
+
+    /// Optimized loops take fewer steps, so they are only compared on programs that the plain
+    /// interpreter finishes, with or without an error.
+    #[test]
+    fn optimizations_match_interpreter(
+        program in program(),
+        width in width(),
+        overflow_policy in overflow_policy(),
+        a in register_value(),
+        b in register_value(),
+    ) {
+        let computer = || {
+            let mut computer = Computer::new(&source(&program)).unwrap();
+            computer
+                .set_width(width)
+                .set_overflow_policy(overflow_policy)
+                .set_registers([a, b, 0, 0]);
+            computer
+        };
+
+        let mut interpreted = computer();
+        let expected = interpreted.run_with_limit(MAX_STEPS).map(|_| ());
+        if let Err(ComputerError::StepLimitExceeded(_)) = expected {
+            return Ok(());
+        }
+
+        for compiled in [false, true] {
+            let mut optimized = computer();
+            optimized.optimize();
+            let actual = if compiled {
+                optimized.run_compiled().map(|_| ())
+            } else {
+                optimized.run_with_limit(MAX_STEPS).map(|_| ())
+            };
+            prop_assert_eq!(format!("{:?}", actual), format!("{:?}", expected));
+            prop_assert_eq!(optimized.registers(), interpreted.registers());
+            prop_assert_eq!(optimized.output(), interpreted.output());
+            prop_assert_eq!(optimized.instruction_pointer(), interpreted.instruction_pointer());
+        }
+    }
+
+    /// The compiled backend has no step limit, so it only runs programs that the interpreter
+    /// finishes, with or without an error.
+    #[test]
+    fn compiled_matches_interpreter(
+        program in program(),
+        width in width(),
+        overflow_policy in overflow_policy(),
+        a in register_value(),
+        b in register_value(),
+    ) {
+        let computer = || {
+            let mut computer = Computer::new(&source(&program)).unwrap();
+            computer
+                .set_width(width)
+                .set_overflow_policy(overflow_policy)
+                .set_registers([a, b, 0, 0]);
+            computer
+        };
+
+        let mut interpreted = computer();
+        let expected = interpreted.run_with_limit(MAX_STEPS).map(|_| ());
+        if let Err(ComputerError::StepLimitExceeded(_)) = expected {
+            return Ok(());
+        }
+
+        let mut compiled = computer();
+        let actual = compiled.run_compiled().map(|_| ());
+        prop_assert_eq!(format!("{:?}", actual), format!("{:?}", expected));
+        prop_assert_eq!(compiled.registers(), interpreted.registers());
+        prop_assert_eq!(compiled.output(), interpreted.output());
+        prop_assert_eq!(compiled.instruction_pointer(), interpreted.instruction_pointer());
+    }
+}
@@ -0,0 +1,255 @@
+use advent_of_code_2016::counter::column::ColumnCounter;
+use advent_of_code_2016::counter::{ArrayCounter, Counter, OrderedCounter, TieBreak};
+use std::cmp::Ordering;
+
+fn counter(s: &str) -> Counter<char> {
+    s.chars().collect()
+}
+
+#[test]
+fn queries() {
+    let counter = counter("abracadabra");
+    assert_eq!(counter.get(&'a'), 5);
+    assert_eq!(counter.get(&'z'), 0);
+    assert_eq!(counter.total(), 11);
+    assert_eq!(counter.len(), 5);
+    assert!(!counter.is_empty());
+    assert!(Counter::<char>::new().is_empty());
+
+    let mut counts: Vec<_> = counter.iter().map(|(&c, n)| (c, n)).collect();
+    counts.sort();
+    assert_eq!(counts, [('a', 5), ('b', 2), ('c', 1), ('d', 1), ('r', 2)]);
+}
+
+#[test]
+fn ties_by_insertion() {
+    let counter = counter("zyxzyxw");
+    assert_eq!(counter.most_common(), Some(&'z'));
+    assert_eq!(counter.least_common(), Some(&'w'));
+    assert_eq!(counter.most_common_n(3), [(&'z', 2), (&'y', 2), (&'x', 2)]);
+    assert_eq!(counter.least_common_n(2), [(&'w', 1), (&'z', 2)]);
+}
+
+#[test]
+fn ties_by_key() {
+    let mut counter = counter("zyxzyxw");
+    counter.set_tie_break(TieBreak::key());
+    assert_eq!(counter.most_common(), Some(&'x'));
+    assert_eq!(
+        counter.most_common_n(10),
+        [(&'x', 2), (&'y', 2), (&'z', 2), (&'w', 1)]
+    );
+}
+
+#[test]
+fn ties_by_comparator() {
+    let mut counter = counter("zyxzyxw");
+    counter.set_tie_break(TieBreak::by(|a: &char, b: &char| b.cmp(a)));
+    assert_eq!(counter.most_common_n(2), [(&'z', 2), (&'y', 2)]);
+    assert_eq!(counter.least_common_n(0), []);
+
+    // Only counts matter when they differ.
+    counter.set_tie_break(TieBreak::by(|_: &char, _: &char| Ordering::Less));
+    assert_eq!(counter.least_common(), Some(&'w'));
+}
+
+#[test]
+fn day4_checksum() {
+    let mut counter = counter("aaaaabbbzyx");
+    counter.set_tie_break(TieBreak::key());
+    let checksum: String = counter
+        .most_common_n(5)
+        .into_iter()
+        .map(|(c, _)| c)
+        .collect();
+    assert_eq!(checksum, "abxyz");
+}
+
+fn counts(counter: &Counter<char>) -> Vec<(char, usize)> {
+    let mut counts: Vec<_> = counter.iter().map(|(&c, n)| (c, n)).collect();
+    counts.sort();
+    counts
+}
+
+#[test]
+fn multiset_operations() {
+    assert_eq!(
+        counts(&(counter("aab") + counter("abc"))),
+        [('a', 3), ('b', 2), ('c', 1)]
+    );
+    assert_eq!(counts(&(counter("aab") - counter("abbc"))), [('a', 1)]);
+    assert_eq!(
+        counts(&(counter("aab") | counter("abbc"))),
+        [('a', 2), ('b', 2), ('c', 1)]
+    );
+    assert_eq!(
+        counts(&(counter("aab") & counter("abbc"))),
+        [('a', 1), ('b', 1)]
+    );
+
+    // Anagrams have the same profile.
+    assert!((counter("listen") - counter("silent")).is_empty());
+    assert!((counter("silent") - counter("listen")).is_empty());
+}
+
+#[test]
+fn update_and_subtract() {
+    let mut counter = counter("ab");
+    counter.update("bc".chars());
+    counter.extend("c".chars());
+    assert_eq!(counts(&counter), [('a', 1), ('b', 2), ('c', 2)]);
+
+    counter.subtract("aabc".chars());
+    assert_eq!(counts(&counter), [('b', 1), ('c', 1)]);
+    assert_eq!(counter.get(&'a'), 0);
+    assert_eq!(counter.len(), 2);
+}
+
+#[test]
+fn elements() {
+    let mut elements: Vec<_> = counter("banana").elements().copied().collect();
+    elements.sort();
+    assert_eq!(elements, ['a', 'a', 'a', 'b', 'n', 'n']);
+}
+
+#[test]
+fn ordered() {
+    let counter: OrderedCounter<&str> = "to be or not to be".split(' ').collect();
+    assert_eq!(
+        counter.iter().collect::<Vec<_>>(),
+        [(&"be", 2), (&"not", 1), (&"or", 1), (&"to", 2)]
+    );
+    assert_eq!(counter.most_common_n(2), [(&"to", 2), (&"be", 2)]);
+    assert_eq!(
+        counter.into_iter().map(|(k, _)| k).collect::<Vec<_>>(),
+        ["be", "not", "or", "to"]
+    );
+}
+
+#[test]
+fn array() {
+    let mut counter: ArrayCounter<u8> = b"hello world".iter().copied().collect();
+    assert_eq!(counter.get(&b'l'), 3);
+    assert_eq!(counter.len(), 8);
+    assert_eq!(counter.most_common_n(2), [(&b'l', 3), (&b'o', 2)]);
+    assert_eq!(counter.elements().next(), Some(&b' '));
+
+    counter.subtract(b"lll".iter().copied());
+    assert_eq!(counter.get(&b'l'), 0);
+    assert_eq!(counter.len(), 7);
+
+    let letters: ArrayCounter<char> = "zebra".chars().collect();
+    assert_eq!(letters.iter().map(|(&c, _)| c).collect::<String>(), "aberz");
+    assert_eq!(letters.get(&'é'), 0);
+}
+
+#[test]
+#[should_panic(expected = "not part of the alphabet")]
+fn array_outside_alphabet() {
+    let _: ArrayCounter<char> = "café".chars().collect();
+}
+
+#[test]
+fn weighted_counts() {
+    let mut counter = Counter::<&str, f64>::new();
+    counter.add_count("a", 0.5);
+    counter.add_count("b", 1.25);
+    counter.add("a");
+    assert_eq!(counter.total(), 2.75);
+    assert_eq!(counter.most_common_n(1), [(&"a", 1.5)]);
+
+    let mut counter = OrderedCounter::<&str, u64>::new();
+    counter.add_count("x", u64::MAX - 1);
+    counter.add("x");
+    assert_eq!(counter.get(&"x"), u64::MAX);
+}
+
+#[test]
+fn negative_counts() {
+    let mut counter = OrderedCounter::<char, i64>::new();
+    counter.update("ab".chars());
+    counter.subtract("abbc".chars());
+    assert_eq!(counter.iter().collect::<Vec<_>>(), [(&'b', -1), (&'c', -1)]);
+    assert_eq!(counter.total(), -2);
+    assert_eq!(counter.elements().count(), 0);
+
+    // Multiset operations only keep positive counts.
+    let positive: OrderedCounter<char, i64> = "bd".chars().collect();
+    assert_eq!((counter + positive).iter().collect::<Vec<_>>(), [(&'d', 1)]);
+}
+
+#[test]
+fn merge() {
+    let mut merged = counter("ab");
+    merged.merge(counter("bc"));
+    assert_eq!(counts(&merged), [('a', 1), ('b', 2), ('c', 1)]);
+}
+
+#[test]
+fn columns() {
+    let mut columns: ColumnCounter<ArrayCounter<char>> = ["eedadn", "drvtee", "eandsr"]
+        .iter()
+        .map(|l| l.chars())
+        .collect();
+    columns.add_line("raavrd");
+    assert_eq!(columns.columns().len(), 6);
+    assert_eq!(columns.columns()[0].get(&'e'), 2);
+    assert_eq!(columns.most_common().collect::<String>(), "eadadn");
+
+    let mut other = ColumnCounter::<ArrayCounter<char>>::new();
+    other.add_line("aaaaaaaa");
+    other.add_line("aaaaaaaa");
+    columns.merge(other);
+    assert_eq!(columns.columns().len(), 8);
+    assert_eq!(columns.most_common().collect::<String>(), "eaaaaaaa");
+    assert_eq!(columns.least_common().collect::<String>(), "dedtdnaa");
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel() {
+    use rayon::prelude::*;
+
+    let text = "the quick brown fox jumps over the lazy dog".repeat(1000);
+    let bytes = text.as_bytes();
+    let parallel: ArrayCounter<u8> = bytes.par_iter().copied().collect();
+    let sequential: ArrayCounter<u8> = bytes.iter().copied().collect();
+    assert_eq!(
+        parallel.iter().collect::<Vec<_>>(),
+        sequential.iter().collect::<Vec<_>>()
+    );
+
+    let lines: Vec<String> = (0..1000).map(|i| format!("{:05}", i * 7)).collect();
+    let parallel: ColumnCounter<OrderedCounter<char>> = lines
+        .par_iter()
+        .map(|l| l.chars().collect::<Vec<_>>())
+        .collect();
+    let sequential: ColumnCounter<OrderedCounter<char>> = lines.iter().map(|l| l.chars()).collect();
+    for (p, s) in parallel.columns().iter().zip(sequential.columns()) {
+        assert_eq!(p.iter().collect::<Vec<_>>(), s.iter().collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn nan_counts() {
+    let mut counter = Counter::<u32, f64>::new();
+    for i in 0..200 {
+        let weight = match i % 4 {
+            0 => f64::NAN,
+            1 => -f64::NAN,
+            _ => f64::from(i),
+        };
+        counter.add_count(i, weight);
+    }
+
+    // NaN ranks above every number, and a negative NaN below.
+    let most_common = counter.most_common_n(60);
+    assert_eq!(most_common.len(), 60);
+    assert!(most_common[..50].iter().all(|(_, n)| n.is_nan()));
+    assert_eq!(most_common[50], (&199, 199.0));
+    let least_common = counter.least_common_n(200);
+    assert!(least_common[..50].iter().all(|(_, n)| n.is_nan()));
+    assert_eq!(least_common[50], (&2, 2.0));
+    assert_eq!(counter.most_common(), Some(&0));
+    assert_eq!(counter.least_common(), Some(&1));
+}
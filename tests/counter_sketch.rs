@@ -0,0 +1,134 @@
+//! Error bounds of the approximate counters, checked against exact counts.
+
+use advent_of_code_2016::counter::sketch::{CountMinSketch, SpaceSaving};
+use advent_of_code_2016::counter::{Counter, Frequencies};
+
+const STREAM_LEN: usize = 200_000;
+
+/// A deterministic stream in which about half of the elements are 1, a sixth are 2, and so on up
+/// to 1000.
+fn skewed_stream() -> impl Iterator<Item = u32> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    std::iter::repeat_with(move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let r = (state >> 11) as f64 / (1u64 << 53) as f64;
+        (1000.0 / (1.0 + r * 999.0)) as u32
+    })
+    .take(STREAM_LEN)
+}
+
+/// The first two hex digits of MD5 hashes, in the style of days 5 and 14: 256 equally common
+/// elements.
+fn hash_prefixes() -> impl Iterator<Item = String> {
+    (0..20_000).map(|i| format!("{:x}", md5::compute(format!("abc{}", i)))[..2].to_string())
+}
+
+fn hex(prefix: String) -> u32 {
+    u32::from_str_radix(&prefix, 16).unwrap()
+}
+
+fn fill<K, F: Frequencies<K>>(mut frequencies: F, stream: impl Iterator<Item = K>) -> F {
+    for element in stream {
+        frequencies.add(element);
+    }
+    frequencies
+}
+
+fn top_keys<K: Copy, F: Frequencies<K>>(frequencies: &F, n: usize) -> Vec<K> {
+    frequencies
+        .most_common_n(n)
+        .into_iter()
+        .map(|(&k, _)| k)
+        .collect()
+}
+
+#[test]
+fn count_min_sketch_error() {
+    let (epsilon, delta) = (0.01, 0.001);
+
+    for (exact, sketch) in [
+        (
+            fill(Counter::<u32>::new(), skewed_stream()),
+            fill(
+                CountMinSketch::with_error(epsilon, delta, 10),
+                skewed_stream(),
+            ),
+        ),
+        (
+            fill(Counter::<u32>::new(), hash_prefixes().map(hex)),
+            fill(
+                CountMinSketch::with_error(epsilon, delta, 10),
+                hash_prefixes().map(hex),
+            ),
+        ),
+    ] {
+        assert_eq!(sketch.total(), exact.total());
+        let bound = (epsilon * exact.total() as f64) as usize;
+        for (element, n) in exact.iter() {
+            let estimate = sketch.get(element);
+            assert!(
+                n <= estimate && estimate <= n + bound,
+                "{}: {} vs {}",
+                element,
+                estimate,
+                n
+            );
+        }
+    }
+}
+
+#[test]
+fn count_min_sketch_most_common() {
+    let exact = fill(Counter::<u32>::new(), skewed_stream());
+    let sketch = fill(CountMinSketch::new(1000, 5, 10), skewed_stream());
+    assert_eq!(top_keys(&sketch, 5), top_keys(&exact, 5));
+    for (&element, estimate) in sketch.most_common_n(5) {
+        assert!(estimate >= exact.get(&element));
+    }
+}
+
+#[test]
+fn space_saving_error() {
+    let capacity = 100;
+    let exact = fill(Counter::<u32>::new(), skewed_stream());
+    let tracker = fill(SpaceSaving::new(capacity), skewed_stream());
+    assert_eq!(tracker.total(), STREAM_LEN);
+
+    for (element, n) in exact.iter() {
+        let (low, high) = tracker.bounds(element);
+        assert!(
+            low <= n && n <= high,
+            "{}: {} not in {}..={}",
+            element,
+            n,
+            low,
+            high
+        );
+        // Frequent elements are always monitored.
+        if n > STREAM_LEN / capacity {
+            assert!(tracker.get(element) >= n);
+        }
+    }
+
+    let counts: usize = tracker
+        .most_common_n(capacity)
+        .iter()
+        .map(|&(_, n)| n)
+        .sum();
+    assert_eq!(counts, STREAM_LEN);
+}
+
+#[test]
+fn space_saving_most_common() {
+    let exact = fill(Counter::<u32>::new(), skewed_stream());
+    let tracker = fill(SpaceSaving::new(50), skewed_stream());
+    assert_eq!(top_keys(&tracker, 5), top_keys(&exact, 5));
+
+    let prefixes = fill(SpaceSaving::new(256), hash_prefixes());
+    let exact = fill(Counter::<String>::new(), hash_prefixes());
+    for (element, n) in exact.iter() {
+        assert_eq!(prefixes.bounds(element), (n, n));
+    }
+}
@@ -0,0 +1,19 @@
+//! Replays the inputs in `fuzz/regressions/`, each a minimized crash found by the fuzzer.
+
+#[path = "../fuzz/harness.rs"]
+mod harness;
+
+#[test]
+fn fuzz_regressions() -> Result<(), Box<dyn std::error::Error>> {
+    let mut failures = Vec::new();
+    for entry in std::fs::read_dir("fuzz/regressions")? {
+        let path = entry?.path();
+        let data = std::fs::read(&path)?;
+        if std::panic::catch_unwind(|| harness::parse_and_run(&data)).is_err() {
+            failures.push(path.display().to_string());
+        }
+    }
+
+    assert!(failures.is_empty(), "failing inputs: {:?}", failures);
+    Ok(())
+}
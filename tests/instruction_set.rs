@@ -0,0 +1,135 @@
+use advent_of_code_2016::computer::instruction_set::ExtendedInstruction;
+use advent_of_code_2016::computer::{
+    Argument, Computer, ComputerError, Instruction, Machine, OverflowPolicy, Register, Status,
+    Step, Value, Width,
+};
+use std::convert::TryFrom;
+
+type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+
+/// The register with this name.
+fn register(name: &str) -> Register {
+    Register::try_from(name).unwrap()
+}
+
+/// Factorial of `a`, in `b`.
+const FACTORIAL: &str = "
+    cpy 1 b
+    mul b a     ; loop
+    dec a
+    jnz a -2
+    nop
+";
+
+#[test]
+fn multiply() -> Result {
+    let mut machine = Machine::<ExtendedInstruction>::new(FACTORIAL)?;
+    machine.set_registers([10]).run()?;
+    assert_eq!(machine.registers(), [0, 3_628_800, 0, 0]);
+    assert!(machine.is_halted());
+
+    // Multiplying a value does nothing.
+    let mut machine = Machine::<ExtendedInstruction>::new("mul 2 3\ninc a")?;
+    assert_eq!(machine.run()?.registers(), [1, 0, 0, 0]);
+    Ok(())
+}
+
+#[test]
+fn multiply_overflow() -> Result {
+    let mut machine = Machine::<ExtendedInstruction>::new(FACTORIAL)?;
+    machine.set_registers([13]);
+    assert!(matches!(machine.run(), Err(ComputerError::Overflow(1))));
+
+    let mut machine = Machine::<ExtendedInstruction>::new(FACTORIAL)?;
+    machine.set_width(Width::I64).set_registers([20]).run()?;
+    assert_eq!(machine.register(register("b")), 2_432_902_008_176_640_000);
+
+    let mut machine = Machine::<ExtendedInstruction>::new(FACTORIAL)?;
+    machine
+        .set_overflow_policy(OverflowPolicy::Saturate)
+        .set_registers([13])
+        .run()?;
+    assert_eq!(machine.register(register("b")), i128::from(i32::MAX));
+    Ok(())
+}
+
+#[test]
+fn send_and_receive() -> Result {
+    let mut machine = Machine::<ExtendedInstruction>::with_registers(
+        "rcv x\nsnd x\nmul x x\nsnd x\njnz 1 -4",
+        "x",
+    )?;
+    assert!(matches!(machine.run(), Err(ComputerError::NoInput(0))));
+    assert_eq!(
+        machine.step()?,
+        Step::Blocked {
+            instruction_pointer: 0
+        }
+    );
+    assert!(machine.output().is_empty());
+
+    machine.input(3).input(-4);
+    assert_eq!(machine.resume()?, Status::Output(3));
+    assert!(matches!(machine.run(), Err(ComputerError::NoInput(0))));
+    assert!(!machine.is_halted());
+    assert_eq!(machine.output(), [3, 9, -4, 16]);
+    Ok(())
+}
+
+#[test]
+fn parse() -> Result {
+    let machine = Machine::<ExtendedInstruction>::new("nop\nmul a 2\nsnd 1\nrcv d")?;
+    assert_eq!(
+        machine.program(),
+        [
+            ExtendedInstruction::Nop,
+            ExtendedInstruction::Multiply(
+                Argument::Register(register("a")),
+                Argument::Value(Value(2))
+            ),
+            ExtendedInstruction::Send(Argument::Value(Value(1))),
+            ExtendedInstruction::Receive(Argument::Register(register("d"))),
+        ]
+    );
+    assert_eq!(
+        machine
+            .program()
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>(),
+        ["nop", "mul a 2", "snd 1", "rcv d"]
+    );
+
+    // Assembunny-only instructions are not part of the set, and the other way around.
+    for (program, opcode) in [("tgl a", "tgl"), ("out a", "out")] {
+        match Machine::<ExtendedInstruction>::new(program) {
+            Err(ComputerError::Parse(errors)) => {
+                assert!(errors[0].to_string().contains(opcode), "{}", errors[0])
+            }
+            _ => panic!("{} should not parse", program),
+        }
+    }
+    assert!(Computer::new("mul a 2").is_err());
+    Ok(())
+}
+
+#[test]
+fn assembunny_machine() -> Result {
+    let program = "cpy 2 a\ncpy a b\nout b\ndec a\njnz a -3\ntgl 2\ninc c\ndec c";
+    let mut computer = Computer::new(program)?;
+    computer.run()?;
+    let mut machine = Machine::<Instruction>::new(program)?;
+    machine.run()?;
+
+    assert_eq!(machine.registers(), [0, 1, 2, 0]);
+    assert_eq!(machine.registers(), computer.registers());
+    assert_eq!(machine.output(), computer.output());
+    assert_eq!(machine.program(), computer.program());
+    // Like `Computer`, undeclared registers read as 0.
+    assert_eq!(machine.register(register("z")), 0);
+    assert_eq!(
+        machine.register(register("z")),
+        computer.register(register("z"))
+    );
+    Ok(())
+}
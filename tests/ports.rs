@@ -0,0 +1,102 @@
+use advent_of_code_2016::computer::{Computer, ComputerError, Status};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+
+type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+
+/// Doubles every (positive) value it reads, until it reads 0.
+const DOUBLER: &str = "in a\njnz a 2\njnz 1 7\ncpy a b\ninc a\ndec b\njnz b -2\nout a\njnz 1 -8";
+
+#[test]
+fn resume() -> Result {
+    let mut computer = Computer::new(DOUBLER)?;
+    assert_eq!(computer.resume()?, Status::Blocked);
+    assert_eq!(computer.instruction_pointer(), 0);
+
+    computer.input(3).input(2);
+    assert_eq!(computer.resume()?, Status::Output(6));
+    assert_eq!(computer.resume()?, Status::Output(4));
+    assert_eq!(computer.resume()?, Status::Blocked);
+    assert_eq!(computer.resume()?, Status::Blocked);
+
+    computer.input(0);
+    assert_eq!(computer.resume()?, Status::Halted);
+    assert_eq!(computer.output(), [6, 4]);
+    Ok(())
+}
+
+#[test]
+fn run_needs_input() -> Result {
+    let mut computer = Computer::new(DOUBLER)?;
+    computer.input(5);
+    assert!(matches!(computer.run(), Err(ComputerError::NoInput(0))));
+    assert_eq!(computer.output(), [10]);
+    Ok(())
+}
+
+#[test]
+fn iterator_and_sink() -> Result {
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&sent);
+    let mut computer = Computer::new(DOUBLER)?;
+    computer
+        .input(1)
+        .set_input(vec![2, 3, 0])
+        .set_output(move |v| sink.lock().unwrap().push(v))
+        .run()?;
+    assert_eq!(computer.output(), [2, 4, 6]);
+    assert_eq!(*sent.lock().unwrap(), [2, 4, 6]);
+    Ok(())
+}
+
+#[test]
+fn channels_between_threads() -> Result {
+    let (to_first, first_input) = channel();
+    let (first_output, to_second) = channel();
+    let (second_output, results) = channel();
+
+    let mut first = Computer::new(DOUBLER)?;
+    first
+        .connect_input(first_input)
+        .connect_output(first_output);
+    let mut second = Computer::new(DOUBLER)?;
+    second
+        .connect_input(to_second)
+        .connect_output(second_output);
+
+    let first = std::thread::spawn(move || first.run().map(|c| c.output().to_vec()));
+    let second = std::thread::spawn(move || second.run().map(|c| c.output().to_vec()));
+    for v in [1, 7, 3] {
+        to_first.send(v)?;
+        assert_eq!(results.recv()?, 4 * v);
+    }
+    drop(to_first);
+
+    // Once its input is closed, the first computer fails, and then so does the second.
+    assert!(matches!(
+        first.join().unwrap(),
+        Err(ComputerError::NoInput(0))
+    ));
+    assert!(matches!(
+        second.join().unwrap(),
+        Err(ComputerError::NoInput(0))
+    ));
+    assert!(results.recv().is_err());
+    Ok(())
+}
+
+#[test]
+fn resume_with_channels() -> Result {
+    let (sender, receiver) = channel();
+    let mut computer = Computer::new(DOUBLER)?;
+    computer.connect_input(receiver);
+    assert_eq!(computer.resume()?, Status::Blocked);
+
+    sender.send(4)?;
+    assert_eq!(computer.resume()?, Status::Output(8));
+    // Resuming never waits, even when the channel is still open.
+    assert_eq!(computer.resume()?, Status::Blocked);
+    sender.send(0)?;
+    assert_eq!(computer.resume()?, Status::Halted);
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use advent_of_code_2016::computer::{Computer, ComputerError, Register};
+use std::convert::TryFrom;
+
+type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+
+/// The register with this name.
+fn register(name: &str) -> Register {
+    Register::try_from(name).unwrap()
+}
+
+#[test]
+fn candidates_are_detached_from_ports() -> Result {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut computer = Computer::new("in b\nout a\nout b\nin c")?;
+    computer
+        .input(1)
+        .set_input(vec![5, 6])
+        .connect_output(sender);
+
+    let (value, candidate) = computer
+        .search(register("a"), 0..10)?
+        .threads(4)
+        .minimal(|computer, result| {
+            computer.output() == [7, 1] && matches!(result, Err(ComputerError::NoInput(3)))
+        })
+        .expect("7 should match");
+    assert_eq!(value, 7);
+    assert_eq!(candidate.registers(), [7, 1, 0, 0]);
+    assert!(receiver.try_recv().is_err());
+
+    // The computer itself still has all of its input.
+    computer.run()?;
+    assert_eq!(computer.registers(), [0, 1, 5, 0]);
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [0, 1]);
+    Ok(())
+}
+
+/// Transmits `a + 1`, then loops forever if `a` is 0.
+const PROGRAM: &str = "inc a\nout a\ndec a\njnz a 2\njnz 1 0";
+
+fn multiple_of_seven(computer: &Computer, result: &std::result::Result<(), ComputerError>) -> bool {
+    result.is_ok() && computer.output()[0] % 7 == 0
+}
+
+#[test]
+fn minimal() -> Result {
+    let computer = Computer::new(PROGRAM)?;
+    for threads in 1..=4 {
+        let (value, found) = computer
+            .search(register("a"), 1..100)?
+            .threads(threads)
+            .minimal(multiple_of_seven)
+            .expect("there are multiples of 7");
+        assert_eq!(value, 6);
+        assert_eq!(found.output(), [7]);
+    }
+    assert!(computer
+        .search(register("a"), 1..6)?
+        .minimal(multiple_of_seven)
+        .is_none());
+    assert!(computer
+        .search(register("a"), 5..5)?
+        .minimal(|_, _| true)
+        .is_none());
+    Ok(())
+}
+
+#[test]
+fn first() -> Result {
+    let computer = Computer::new(PROGRAM)?;
+    let (value, found) = computer
+        .search(register("a"), 1..100)?
+        .threads(4)
+        .first(multiple_of_seven)
+        .expect("there are multiples of 7");
+    assert_eq!((value + 1) % 7, 0);
+    assert_eq!(found.output(), [value + 1]);
+    Ok(())
+}
+
+#[test]
+fn limits() -> Result {
+    let mut computer = Computer::new(PROGRAM)?;
+    computer.set_registers([0, 1, 2, 3]);
+    let search = || computer.search(register("a"), -1..2);
+
+    // Without a limit, 0 would run forever.
+    let (value, found) = search()?
+        .max_steps(100)
+        .minimal(|_, result| matches!(result, Err(ComputerError::StepLimitExceeded(100))))
+        .unwrap();
+    assert_eq!(value, 0);
+    assert_eq!(found.registers(), [0, 1, 2, 3]);
+
+    let (value, _) = search()?
+        .stop_when(|computer| computer.output() == [1])
+        .minimal(|computer, result| result.is_ok() && computer.is_halted())
+        .unwrap();
+    assert_eq!(value, -1);
+
+    assert!(matches!(
+        computer.search(register("e"), 0..1),
+        Err(ComputerError::InvalidRegister(_))
+    ));
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use advent_of_code_2016::computer::{Computer, ComputerError, Register};
+use std::convert::TryFrom;
+
+/// The register with this name.
+fn register(name: &str) -> Register {
+    Register::try_from(name).unwrap()
+}
+
+#[test]
+fn day12_closed_form() {
+    let computer = Computer::new(include_str!("../inputs/day12.txt")).unwrap();
+
+    let summary = computer.evaluate_symbolically(&[]).unwrap();
+    assert_eq!(
+        summary.register(register("a")).unwrap().as_constant(),
+        Some(318083)
+    );
+
+    let summary = computer.evaluate_symbolically(&[register("c")]).unwrap();
+    assert_eq!(
+        summary.register(register("a")).unwrap().as_constant(),
+        Some(9227737)
+    );
+    let assumptions: Vec<String> = summary
+        .assumptions()
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    assert_eq!(assumptions, ["c != 0"]);
+}
+
+#[test]
+fn day23_closed_form() {
+    let computer = Computer::new(include_str!("../inputs/day23.txt")).unwrap();
+    let summary = computer.evaluate_symbolically(&[register("a")]).unwrap();
+
+    let a = summary.register(register("a")).unwrap();
+    assert_eq!(a.to_string(), "a! + 6090");
+    assert_eq!(a.evaluate(|_| 7), Some(11130));
+    assert_eq!(a.evaluate(|_| 12), Some(479007690));
+    let assumptions: Vec<String> = summary
+        .assumptions()
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    assert_eq!(assumptions, ["a >= 7"]);
+}
+
+#[test]
+fn loops_are_summarized() {
+    // b += a * d, then a multiplied by a countdown from b.
+    let computer = Computer::new("cpy a c\ninc b\ndec c\njnz c -2\ndec d\njnz d -5").unwrap();
+    let summary = computer
+        .evaluate_symbolically(&[register("a"), register("d")])
+        .unwrap();
+    assert_eq!(summary.register(register("b")).unwrap().to_string(), "ad");
+    assert_eq!(
+        summary.register(register("c")).unwrap().as_constant(),
+        Some(0)
+    );
+}
+
+#[test]
+fn coefficient_overflow() {
+    for program in [
+        "cpy 170141183460469231731687303715884105727 a\ninc a",
+        "cpy -170141183460469231731687303715884105728 a\ndec a",
+        "cpy -170141183460469231731687303715884105728 b\ncpy b c\ninc a\ndec c\njnz c -2",
+    ] {
+        let computer = Computer::new(program).unwrap();
+        match computer.evaluate_symbolically(&[]) {
+            Err(ComputerError::NotSymbolic { reason, .. }) => {
+                assert_eq!(reason, "coefficient overflow", "{}", program)
+            }
+            r => panic!("{}: {:?}", program, r.map(|s| s.to_string())),
+        }
+    }
+}
+
+#[test]
+fn unsupported_instructions() {
+    let computer = Computer::new("in a").unwrap();
+    assert!(matches!(
+        computer.evaluate_symbolically(&[]),
+        Err(ComputerError::NotSymbolic {
+            instruction_pointer: 0,
+            reason: "in"
+        })
+    ));
+    assert!(matches!(
+        computer.evaluate_symbolically(&[register("h")]),
+        Err(ComputerError::InvalidRegister(_))
+    ));
+}